@@ -1,90 +1,92 @@
-use std::io::Cursor;
-
-use rustybuzz::{ShapePlan, UnicodeBuffer, shape_with_plan};
 use ttf_parser::GlyphId;
 
+use crate::config::StrikeSelection;
+use crate::manifest::OutputFile;
 use crate::provider::{ImageProvider, Provider};
+use crate::raster;
+use crate::shaping::Shaper;
 use crate::{EmojiImage, Error};
 
-fn parse_hex<'a>(hex: &str, buffer: &'a mut [u8]) -> &'a mut str {
-    let mut size = 0;
-    for c in hex.split('-') {
-        let c = u32::from_str_radix(c, 16)
-            .ok()
-            .and_then(char::from_u32)
-            .unwrap();
-        size += c.encode_utf8(&mut buffer[size..]).len();
-    }
-    // this will always work
-    std::str::from_utf8_mut(&mut buffer[..size]).unwrap()
-}
-
 pub struct AppleFont<'a> {
-    face: rustybuzz::Face<'a>,
-    plan: rustybuzz::ShapePlan,
-    strike: ttf_parser::sbix::Strike<'a>,
+    shaper: Shaper<'a>,
+    /// Strikes sorted by their size
+    strikes: Vec<ttf_parser::sbix::Strike<'a>>,
+    selection: StrikeSelection,
 }
 
 impl ImageProvider for AppleFont<'_> {
     fn read_image(&self, emoji: &EmojiImage) -> Result<image::DynamicImage, Error> {
-        let mut unicode_buffer = UnicodeBuffer::new();
-        let mut utf8_buffer = [0u8; 64];
-        unicode_buffer.push_str(parse_hex(emoji.unified, &mut utf8_buffer));
-        let glyphs = shape_with_plan(&self.face, &self.plan, unicode_buffer);
-        if glyphs.is_empty() {
-            return Err(Error::NoEmoji);
-        }
-
-        if glyphs.len() > 1 {
-            let mut img = None;
-            for glyph in glyphs.glyph_infos() {
-                let raster_img = self
-                    .strike
-                    .get(GlyphId(glyph.glyph_id as u16))
-                    .ok_or_else(|| Error::NoEmoji)?;
-
-                let mut reader = image::ImageReader::new(Cursor::new(raster_img.data));
-                reader.set_format(image::ImageFormat::Png);
-                let decoded = reader.decode()?;
-                if let Some(ref mut bg) = img {
-                    image::imageops::overlay(bg, &decoded, 0, 0);
-                } else {
-                    img = Some(decoded);
-                }
-            }
-
-            img.ok_or_else(|| Error::NoEmoji)
-        } else {
-            let id = GlyphId(glyphs.glyph_infos()[0].glyph_id as u16);
-            let img = self.strike.get(id).ok_or_else(|| Error::NoEmoji)?;
-            let mut reader = image::ImageReader::new(Cursor::new(img.data));
-            reader.set_format(image::ImageFormat::Png);
-            reader.decode().map_err(Into::into)
-        }
+        let glyphs = self.shaper.shape(emoji)?;
+        let images = raster::glyph_images(&glyphs, |id| self.glyph(id, u32::MAX))?;
+        raster::composite(&glyphs, &images, self.shaper.face().units_per_em())
     }
 }
 
 impl Provider for AppleFont<'_> {
+    fn read_source(&self, emoji: &EmojiImage) -> Result<Vec<u8>, Error> {
+        let glyphs = self.shaper.shape(emoji)?;
+        let mut source = vec![self.selection as u8];
+        if self.selection == StrikeSelection::Largest {
+            let images = raster::glyph_images(&glyphs, |id| self.glyph(id, u32::MAX))?;
+            source.extend(raster::source(&glyphs, &images));
+            return Ok(source);
+        }
+        // every strike containing one of the glyphs can be used for some size
+        let mut found = false;
+        for strike in &self.strikes {
+            let images = match raster::glyph_images(&glyphs, |id| strike.get(id)) {
+                Ok(images) => images,
+                Err(Error::NoEmoji) => continue,
+                Err(e) => return Err(e),
+            };
+            source.extend(raster::source(&glyphs, &images));
+            found = true;
+        }
+        if !found {
+            return Err(Error::NoEmoji);
+        }
+        Ok(source)
+    }
+
     fn transform(
         &self,
         options: &crate::Options,
         emoji: &EmojiImage,
-        sizes: &[u32],
-    ) -> Result<(), Error> {
-        self.transform_image(options, emoji, sizes)
+        sizes: &[crate::Size],
+    ) -> Result<Vec<OutputFile>, Error> {
+        let glyphs = self.shaper.shape(emoji)?;
+        let units_per_em = self.shaper.face().units_per_em();
+        raster::transform(options, emoji, sizes, &glyphs, units_per_em, |size| {
+            let size = match self.selection {
+                StrikeSelection::Nearest => size,
+                StrikeSelection::Largest => u32::MAX,
+            };
+            raster::glyph_images(&glyphs, |id| self.glyph(id, size))
+        })
     }
 }
 
 impl<'a> AppleFont<'a> {
-    pub fn new(data: &'a [u8], face_index: u32) -> Option<Self> {
-        let face = rustybuzz::Face::from_slice(&data, face_index)?;
-        let plan = ShapePlan::new(&face, rustybuzz::Direction::LeftToRight, None, None, &[]);
-        let strike = face
-            .tables()
-            .sbix?
-            .strikes
-            .into_iter()
-            .max_by_key(|x| x.pixels_per_em)?;
-        Some(Self { face, plan, strike })
+    pub fn new(data: &'a [u8], face_index: u32, selection: StrikeSelection) -> Option<Self> {
+        let shaper = Shaper::new(data, face_index)?;
+        let mut strikes: Vec<_> = shaper.face().tables().sbix?.strikes.into_iter().collect();
+        if strikes.is_empty() {
+            return None;
+        }
+        strikes.sort_by_key(|it| it.pixels_per_em);
+        Some(Self {
+            shaper,
+            strikes,
+            selection,
+        })
+    }
+
+    /// Gets a glyph from the smallest strike with at least `size` pixels per em (or the largest
+    /// one), skipping strikes that don't contain it (like `cbdt::Table::get`).
+    fn glyph(&self, id: GlyphId, size: u32) -> Option<ttf_parser::RasterGlyphImage<'a>> {
+        let images = || self.strikes.iter().filter_map(|strike| strike.get(id));
+        images()
+            .find(|it| u32::from(it.pixels_per_em) >= size)
+            .or_else(|| images().last())
     }
 }
@@ -0,0 +1,208 @@
+use std::{collections::BTreeMap, path::PathBuf, sync::Mutex};
+
+use image::{GenericImage, RgbaImage};
+
+use crate::{EmojiImage, Error, Options, Size, config::AtlasSettings, manifest::OutputFile};
+
+/// Collects the rendered emojis of a vendor and packs them into atlases (sprite sheets).
+///
+/// Emojis are placed on a grid ordered by their unified codepoints. The grid (columns and rows
+/// per page) is derived from the largest size, so an emoji has the same cell in the atlases of
+/// every size.
+pub struct Atlas {
+    settings: AtlasSettings,
+    /// Rendered images by size and unified codepoints
+    images: Mutex<BTreeMap<Size, BTreeMap<String, Sprite>>>,
+}
+
+struct Sprite {
+    name: String,
+    image: RgbaImage,
+}
+
+/// Describes the atlases of a single size, written to `{vendor}/atlas-{size}.json`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Index {
+    pub size: Size,
+    pub pages: Vec<Page>,
+    /// Location of every emoji by its unified codepoints
+    pub emojis: BTreeMap<String, Rect>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Page {
+    pub width: u32,
+    pub height: u32,
+    /// The encoded page, one file per output
+    pub files: Vec<OutputFile>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Rect {
+    /// Short name of the emoji (see [`EmojiImage::name`])
+    pub short_name: String,
+    /// Index into [`Index::pages`]
+    pub page: usize,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Dimensions of the grid shared by all sizes.
+struct Grid {
+    columns: u32,
+    rows: u32,
+}
+
+impl Atlas {
+    pub fn new(settings: AtlasSettings) -> Self {
+        Self {
+            settings,
+            images: Mutex::default(),
+        }
+    }
+
+    pub fn settings(&self) -> &AtlasSettings {
+        &self.settings
+    }
+
+    /// Adds the rendered image of `emoji` for `size`.
+    pub fn add(&self, size: Size, emoji: &EmojiImage, image: RgbaImage) {
+        self.images.lock().unwrap().entry(size).or_default().insert(
+            emoji.unified.to_owned(),
+            Sprite {
+                name: emoji.name(),
+                image,
+            },
+        );
+    }
+
+    /// Packs all collected images, encodes the pages with every output of `options`, and writes
+    /// an index for each size.
+    pub fn write(&self, options: &Options) -> Result<Vec<Index>, Error> {
+        let images = self.images.lock().unwrap();
+        let Some(grid) = self.grid(&images) else {
+            return Ok(Vec::new());
+        };
+
+        let mut indices = Vec::with_capacity(images.len());
+        for (&size, images) in images.iter() {
+            let index = self.write_size(options, &grid, size, images)?;
+            let path = options.outputs()[0]
+                .directories
+                .provider_dir()
+                .join(format!("atlas-{size}.json"));
+            fs_err::write(path, serde_json::to_vec_pretty(&index)?)?;
+            indices.push(index);
+        }
+        Ok(indices)
+    }
+
+    fn grid(&self, images: &BTreeMap<Size, BTreeMap<String, Sprite>>) -> Option<Grid> {
+        let count = images.values().map(|it| it.len()).max()? as u32;
+        let (width, height) = images
+            .values()
+            .flat_map(|it| it.values())
+            .map(|it| it.image.dimensions())
+            .fold((1, 1), |(w, h), (iw, ih)| (w.max(iw), h.max(ih)));
+        let padding = self.settings.padding;
+
+        let max_columns = ((self.settings.max_size + padding) / (width + padding)).max(1);
+        let columns = (count as f64).sqrt().ceil().clamp(1.0, max_columns as f64) as u32;
+        let max_rows = ((self.settings.max_size + padding) / (height + padding)).max(1);
+        let rows = count.div_ceil(columns).min(max_rows);
+        Some(Grid { columns, rows })
+    }
+
+    fn write_size(
+        &self,
+        options: &Options,
+        grid: &Grid,
+        size: Size,
+        images: &BTreeMap<String, Sprite>,
+    ) -> Result<Index, Error> {
+        let (cell_width, cell_height) = images
+            .values()
+            .map(|it| it.image.dimensions())
+            .fold((1, 1), |(w, h), (iw, ih)| (w.max(iw), h.max(ih)));
+        let padding = self.settings.padding;
+        let per_page = (grid.columns * grid.rows) as usize;
+
+        let mut pages = Vec::new();
+        let mut emojis = BTreeMap::new();
+        let entries: Vec<_> = images.iter().collect();
+        for (page_index, chunk) in entries.chunks(per_page).enumerate() {
+            let rows = (chunk.len() as u32).div_ceil(grid.columns);
+            let columns = grid.columns.min(chunk.len() as u32);
+            let width = columns * (cell_width + padding) - padding;
+            let height = rows * (cell_height + padding) - padding;
+            let mut page = RgbaImage::new(width, height);
+
+            for (i, (unified, sprite)) in chunk.iter().enumerate() {
+                let x = (i as u32 % grid.columns) * (cell_width + padding);
+                let y = (i as u32 / grid.columns) * (cell_height + padding);
+                let image = &sprite.image;
+                page.copy_from(image, x, y)?;
+                emojis.insert(
+                    (*unified).clone(),
+                    Rect {
+                        short_name: sprite.name.clone(),
+                        page: page_index,
+                        x,
+                        y,
+                        width: image.width(),
+                        height: image.height(),
+                    },
+                );
+            }
+
+            let files = write_page(options, size, page_index, page)?;
+            pages.push(Page {
+                width,
+                height,
+                files,
+            });
+        }
+
+        Ok(Index {
+            size,
+            pages,
+            emojis,
+        })
+    }
+}
+
+/// Encodes a page with all optimizers.
+fn write_page(
+    options: &Options,
+    size: Size,
+    page_index: usize,
+    page: RgbaImage,
+) -> Result<Vec<OutputFile>, Error> {
+    let dimensions = page.dimensions();
+    let image = fast_image_resize::images::Image::from_vec_u8(
+        dimensions.0,
+        dimensions.1,
+        page.into_raw(),
+        fast_image_resize::PixelType::U8x4,
+    )
+    .expect("the page buffer matches its dimensions");
+
+    let mut files = Vec::with_capacity(options.outputs().len());
+    for output in options.outputs() {
+        let data = output.optimizer.optimize_fir(options, image.copy())?;
+        let path = PathBuf::from(output.directories.provider()).join(format!(
+            "atlas-{size}-{page_index}.{}",
+            output.directories.extension()
+        ));
+        files.push(OutputFile::write(
+            output.directories.root(),
+            path,
+            size,
+            dimensions,
+            &data,
+        )?);
+    }
+    Ok(files)
+}
@@ -0,0 +1,66 @@
+use ravif::{AlphaColorMode, ColorSpace, Img};
+use rgb::FromSlice;
+
+use crate::{
+    config::AvifSettings,
+    optimize::{OptimizableImage, Optimizer},
+};
+
+pub struct Avif;
+
+impl Optimizer for Avif {
+    fn name(&self) -> &'static str {
+        "avif"
+    }
+
+    fn optimize_fir(
+        &self,
+        options: &crate::Options,
+        image: fast_image_resize::images::Image,
+    ) -> Result<Vec<u8>, crate::Error> {
+        optimize(options, image)
+    }
+
+    fn optimize_skia(
+        &self,
+        options: &crate::Options,
+        image: tiny_skia::Pixmap,
+    ) -> Result<Vec<u8>, crate::Error> {
+        optimize(options, image)
+    }
+}
+
+/// Creates the encoder for `settings`.
+///
+/// AV1 is only lossless with the highest quality and without a conversion to YCbCr.
+pub fn encoder(settings: &AvifSettings) -> ravif::Encoder {
+    let encoder = ravif::Encoder::new().with_speed(settings.speed.clamp(1, 10));
+    if settings.lossless {
+        encoder
+            .with_quality(100.0)
+            .with_alpha_quality(100.0)
+            .with_depth(Some(8))
+            .with_internal_color_space(ColorSpace::RGB)
+            .with_alpha_color_mode(AlphaColorMode::UnassociatedDirty)
+    } else {
+        encoder
+            .with_quality(settings.quality.clamp(1.0, 100.0))
+            .with_alpha_quality(settings.alpha_quality.clamp(1.0, 100.0))
+            .with_alpha_color_mode(AlphaColorMode::UnassociatedClean)
+    }
+}
+
+fn optimize(
+    options: &crate::Options,
+    image: impl OptimizableImage,
+) -> Result<Vec<u8>, crate::Error> {
+    let width = image.width() as usize;
+    let height = image.height() as usize;
+    // the alpha is unassociated (not premultiplied)
+    let data = image.into_data();
+    let encoded = options
+        .avif
+        .encode_rgba(Img::new(data.as_rgba(), width, height))
+        .map_err(crate::Error::Avif)?;
+    Ok(encoded.avif_file)
+}
@@ -0,0 +1,86 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use crate::{EmojiImage, manifest::OutputFile};
+
+/// Content-hash cache to skip emojis whose outputs are up to date.
+///
+/// The cache is keyed by the unified codepoints of an emoji. Each entry records the hash of the
+/// source asset and the files that were produced from it. All entries are invalidated if the
+/// `fingerprint` (tool version and settings) changes.
+pub struct Cache {
+    path: PathBuf,
+    manifest: Manifest,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Manifest {
+    fingerprint: String,
+    entries: HashMap<String, Entry>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Entry {
+    source: String,
+    outputs: Vec<OutputFile>,
+}
+
+impl Cache {
+    /// Loads the cache from `path`.
+    ///
+    /// A missing or unreadable cache, or one with a different fingerprint, results in an empty cache.
+    pub fn load(path: PathBuf, fingerprint: &str) -> Self {
+        let manifest = fs_err::read(&path)
+            .ok()
+            .and_then(|data| serde_json::from_slice::<Manifest>(&data).ok())
+            .filter(|it| it.fingerprint == fingerprint)
+            .unwrap_or_else(|| Manifest {
+                fingerprint: fingerprint.to_owned(),
+                entries: HashMap::new(),
+            });
+        Self { path, manifest }
+    }
+
+    /// Returns the files previously produced for `emoji` if its source didn't change.
+    pub fn outputs(&self, emoji: &EmojiImage, source: &str) -> &[OutputFile] {
+        match self.manifest.entries.get(emoji.unified) {
+            Some(entry) if entry.source == source => &entry.outputs,
+            _ => &[],
+        }
+    }
+
+    /// Records that `outputs` of `emoji` were produced from `source`.
+    ///
+    /// Previous outputs for other sizes are kept if the source didn't change.
+    pub fn insert(&mut self, emoji: &EmojiImage, source: String, mut outputs: Vec<OutputFile>) {
+        if let Some(old) = self.manifest.entries.remove(emoji.unified) {
+            if old.source == source {
+                let kept: Vec<_> = old
+                    .outputs
+                    .into_iter()
+                    .filter(|it| !outputs.iter().any(|o| o.size == it.size))
+                    .collect();
+                outputs.extend(kept);
+            }
+        }
+        self.manifest
+            .entries
+            .insert(emoji.unified.to_owned(), Entry { source, outputs });
+    }
+
+    /// Forgets about `emoji`, e.g. because it failed to be produced.
+    pub fn remove(&mut self, emoji: &EmojiImage) {
+        self.manifest.entries.remove(emoji.unified);
+    }
+
+    pub fn save(&self) -> Result<(), crate::Error> {
+        if let Some(parent) = self.path.parent() {
+            fs_err::create_dir_all(parent)?;
+        }
+        fs_err::write(&self.path, serde_json::to_vec(&self.manifest)?).map_err(Into::into)
+    }
+}
+
+/// Hashes `data` (e.g. a source asset) for the cache.
+pub fn hash(data: &[u8]) -> String {
+    blake3::hash(data).to_hex().to_string()
+}
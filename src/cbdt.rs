@@ -0,0 +1,90 @@
+use ttf_parser::GlyphId;
+
+use crate::config::StrikeSelection;
+use crate::manifest::OutputFile;
+use crate::provider::{ImageProvider, Provider};
+use crate::raster;
+use crate::shaping::Shaper;
+use crate::{EmojiImage, Error};
+
+/// Reads emojis from a font with CBDT/CBLC bitmaps (e.g. Noto Color Emoji).
+pub struct CbdtFont<'a> {
+    shaper: Shaper<'a>,
+    cbdt: ttf_parser::cbdt::Table<'a>,
+    selection: StrikeSelection,
+}
+
+impl ImageProvider for CbdtFont<'_> {
+    fn read_image(&self, emoji: &EmojiImage) -> Result<image::DynamicImage, Error> {
+        let glyphs = self.shaper.shape(emoji)?;
+        let images = raster::glyph_images(&glyphs, |id| self.glyph(id, u16::MAX))?;
+        raster::composite(&glyphs, &images, self.shaper.face().units_per_em())
+    }
+}
+
+impl Provider for CbdtFont<'_> {
+    fn read_source(&self, emoji: &EmojiImage) -> Result<Vec<u8>, Error> {
+        let glyphs = self.shaper.shape(emoji)?;
+        let mut source = vec![self.selection as u8];
+        if self.selection == StrikeSelection::Largest {
+            let images = raster::glyph_images(&glyphs, |id| self.glyph(id, u16::MAX))?;
+            source.extend(raster::source(&glyphs, &images));
+            return Ok(source);
+        }
+        // every strike can be used for some size, so walk them from the smallest to the largest
+        let mut pixels_per_em = 0;
+        loop {
+            let images = raster::glyph_images(&glyphs, |id| self.glyph(id, pixels_per_em))?;
+            let strike = images
+                .iter()
+                .flatten()
+                .map(|it| it.pixels_per_em)
+                .max()
+                .unwrap_or(u16::MAX);
+            // `glyph` falls back to the largest strike once there's no larger one
+            if strike < pixels_per_em {
+                break;
+            }
+            source.extend(raster::source(&glyphs, &images));
+            if strike == u16::MAX {
+                break;
+            }
+            pixels_per_em = strike + 1;
+        }
+        Ok(source)
+    }
+
+    fn transform(
+        &self,
+        options: &crate::Options,
+        emoji: &EmojiImage,
+        sizes: &[crate::Size],
+    ) -> Result<Vec<OutputFile>, Error> {
+        let glyphs = self.shaper.shape(emoji)?;
+        let units_per_em = self.shaper.face().units_per_em();
+        raster::transform(options, emoji, sizes, &glyphs, units_per_em, |size| {
+            let pixels_per_em = match self.selection {
+                StrikeSelection::Nearest => size.min(u16::MAX.into()) as u16,
+                StrikeSelection::Largest => u16::MAX,
+            };
+            raster::glyph_images(&glyphs, |id| self.glyph(id, pixels_per_em))
+        })
+    }
+}
+
+impl<'a> CbdtFont<'a> {
+    pub fn new(data: &'a [u8], face_index: u32, selection: StrikeSelection) -> Option<Self> {
+        let shaper = Shaper::new(data, face_index)?;
+        let cbdt = shaper.face().tables().cbdt?;
+        Some(Self {
+            shaper,
+            cbdt,
+            selection,
+        })
+    }
+
+    /// Gets a glyph from the smallest strike with at least `pixels_per_em` (or the largest one).
+    fn glyph(&self, id: GlyphId, pixels_per_em: u16) -> Option<ttf_parser::RasterGlyphImage<'a>> {
+        self.cbdt.get(id, pixels_per_em)
+    }
+}
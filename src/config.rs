@@ -0,0 +1,760 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    Error, Size, Vendor,
+    avif::Avif,
+    directories::Template,
+    emoji::Version,
+    jxl::Jxl,
+    optimize::{Optimizer, Oxipng, Zopflipng},
+    report::FailurePolicy,
+    size::{Density, DensityNaming},
+    webp::Webp,
+};
+
+/// Build configuration, usually read from an `emojicrunch.toml`.
+///
+/// ```toml
+/// emoji_data_root = "emoji-data"
+///
+/// [[target]]
+/// vendors = ["twitter", "google"]
+/// sizes = [22, 44, 88]
+/// format = ["oxipng", "webp"]
+///
+/// [[target]]
+/// format = "zopflipng"
+/// output_dir = "build-png"
+/// zopfli.iterations = 30
+/// ```
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default = "default_emoji_data_root")]
+    pub emoji_data_root: PathBuf,
+    pub apple_font: Option<PathBuf>,
+    /// Render these vendors from a color font (COLR, SVG, sbix, or CBDT) instead of emoji-data
+    #[serde(default)]
+    pub vendor_fonts: HashMap<Vendor, PathBuf>,
+    /// Index of the face in font collections (`.ttc`)
+    #[serde(default)]
+    pub font_index: u32,
+    /// Strikes of bitmap fonts to render from
+    #[serde(default)]
+    pub font_strike: StrikeSelection,
+    /// Vendors to take an emoji from if it's missing from a vendor, in order
+    #[serde(default)]
+    pub fallbacks: HashMap<Vendor, Vec<Vendor>>,
+    #[serde(default, rename = "target")]
+    pub targets: Vec<Target>,
+    #[serde(default)]
+    pub failures: FailurePolicy,
+}
+
+/// A single build target - a set of vendors rendered to a set of sizes with one or more formats.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Target {
+    #[serde(default = "default_vendors")]
+    pub vendors: Vec<Vendor>,
+    /// Output sizes (see [`Size`])
+    #[serde(default = "default_sizes")]
+    pub sizes: Vec<Size>,
+    /// Derive the sizes from a base size and density multipliers (replaces `sizes`)
+    #[serde(default)]
+    pub densities: Option<DensitySettings>,
+    /// Every image is rendered once and encoded to all formats
+    #[serde(
+        default = "default_formats",
+        rename = "format",
+        deserialize_with = "one_or_many"
+    )]
+    pub formats: Vec<Format>,
+    #[serde(default = "default_output_dir")]
+    pub output_dir: PathBuf,
+    /// Path of each image relative to `output_dir` (see [`Template`])
+    #[serde(default)]
+    pub layout: Template,
+    /// Overrides `layout` for specific vendors
+    #[serde(default)]
+    pub vendor_layouts: HashMap<Vendor, Template>,
+    /// Skip emojis whose source and settings didn't change since the last build
+    #[serde(default = "default_true")]
+    pub cache: bool,
+    /// Pack the emojis of each vendor and size into atlases
+    #[serde(default)]
+    pub atlas: Option<AtlasSettings>,
+    /// Write a stylesheet for every size (implies an atlas, see [`crate::css`])
+    #[serde(default)]
+    pub css: bool,
+    /// Build a color bitmap font from the PNGs (see [`crate::fontbuild`])
+    #[serde(default)]
+    pub font: Option<FontSettings>,
+    /// Only build the emojis matching these conditions
+    #[serde(default)]
+    pub filter: FilterSettings,
+    #[serde(flatten)]
+    pub settings: Settings,
+    /// Keys that aren't used by the target or its settings (`deny_unknown_fields` doesn't work
+    /// with `flatten`), rejected by [`Target::validate`]
+    #[serde(flatten)]
+    unknown: BTreeMap<String, serde::de::IgnoredAny>,
+}
+
+/// The encoder/optimizer used to write the images.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// PNG compressed with oxipng
+    #[default]
+    Oxipng,
+    /// PNG compressed with zopflipng
+    Zopflipng,
+    /// WEBP (lossless by default)
+    Webp,
+    /// AVIF (lossy by default)
+    Avif,
+    /// JPEG XL (lossless by default)
+    Jxl,
+}
+
+/// Settings for resizing and the optimizers.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub fit: FitSettings,
+    pub resize: ResizeSettings,
+    pub oxipng: OxipngSettings,
+    pub zopfli: ZopfliSettings,
+    pub webp: WebpSettings,
+    pub avif: AvifSettings,
+    pub jxl: JxlSettings,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OxipngSettings {
+    /// Optimization preset (0-6)
+    pub level: u8,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ZopfliSettings {
+    pub iterations: i32,
+    pub iterations_large: i32,
+    pub lossy_transparent: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebpSettings {
+    /// Encode losslessly (`quality` is the compression effort then)
+    pub lossless: bool,
+    /// Quality of lossy images (0-100)
+    pub quality: f32,
+    /// Preprocessing of lossless images (0 = most lossy, 100 = off)
+    pub near_lossless: i32,
+    /// Quality of the alpha channel of lossy images (0-100)
+    pub alpha_quality: i32,
+    /// Filtering of the alpha channel of lossy images (0 = none, 1 = fast, 2 = best)
+    pub alpha_filtering: i32,
+    /// Preserve the color of transparent pixels
+    pub exact: bool,
+    /// Quality/speed trade-off (0 = fast, 6 = slower but better)
+    pub method: i32,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AvifSettings {
+    /// Encode losslessly (ignores `quality` and `alpha_quality`)
+    pub lossless: bool,
+    /// Quality of the color channels (1-100)
+    pub quality: f32,
+    /// Quality of the alpha channel (1-100)
+    pub alpha_quality: f32,
+    /// Encoding speed (1 = slow but small, 10 = fast but large)
+    pub speed: u8,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JxlSettings {
+    /// Encode losslessly in modular mode (ignores `distance`)
+    pub lossless: bool,
+    /// Butteraugli distance for lossy encoding (0-15, 1.0 = visually lossless)
+    pub distance: f32,
+    /// Encoder effort (1 = fast, 9 = slow but small)
+    pub effort: u8,
+}
+
+/// How emojis are placed in the output images (see [`crate::fit`]).
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FitSettings {
+    pub mode: FitMode,
+    /// Transparent space around the emoji
+    pub padding: Padding,
+    /// Remove transparent borders of the source before fitting it
+    pub trim: bool,
+}
+
+/// How sources are scaled into the output.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    clap::ValueEnum,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum FitMode {
+    /// Keep the aspect ratio and center the source
+    #[default]
+    Contain,
+    /// Stretch the source to the full size
+    Fill,
+}
+
+/// Padding in pixels (`4` or `"4px"`) or in percent of the size (`"10%"`).
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "PaddingValue")]
+pub enum Padding {
+    Pixels(u32),
+    Percent(f32),
+}
+
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum PaddingValue {
+    Pixels(u32),
+    Text(String),
+}
+
+/// Settings for [`crate::resize::resize`] (only used for raster sources).
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ResizeSettings {
+    pub filter: ResizeFilter,
+    /// Resize in linear light instead of sRGB
+    pub linear: bool,
+}
+
+/// The resampling filter used to scale raster images.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    clap::ValueEnum,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResizeFilter {
+    /// Pick a filter based on the scale factor (see [`crate::resize::algorithm`])
+    Auto,
+    #[default]
+    Lanczos3,
+    CatmullRom,
+    Mitchell,
+    Bilinear,
+    /// Averages all pixels covered by the target pixel
+    Box,
+    /// Nearest-neighbor sampling followed by a Lanczos3 convolution (faster)
+    Supersampling,
+    /// Nearest-neighbor sampling (for pixel art)
+    Nearest,
+}
+
+/// Restricts the emojis of a target (see [`crate::emoji::EmojiImage::matches`]).
+///
+/// An emoji has to match all conditions.
+///
+/// ```toml
+/// [target.filter]
+/// since = "15.0"
+/// categories = ["Flags"]
+/// exclude_skin_tones = true
+/// only = ["1f600", "1f44d"]
+/// ```
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FilterSettings {
+    /// Only emojis added in this Emoji version or later
+    pub since: Option<Version>,
+    /// Categories or subcategories (e.g. `Flags` or `face-smiling`)
+    pub categories: Vec<String>,
+    pub exclude_skin_tones: bool,
+    /// Unified codepoints (skin variations of these emojis are included)
+    pub only: Vec<String>,
+}
+
+/// A base size and the densities it's rendered at.
+///
+/// ```toml
+/// [target.densities]
+/// base = 22
+/// scales = [1, 1.5, 2, 3, 4]
+/// naming = "android"
+/// ```
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DensitySettings {
+    /// The size at 1x
+    pub base: Size,
+    pub scales: Vec<f32>,
+    /// How densities are named in the `{density}` placeholder
+    pub naming: DensityNaming,
+}
+
+/// Settings for [`crate::atlas::Atlas`].
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AtlasSettings {
+    /// Maximum width and height of a page
+    pub max_size: u32,
+    /// Transparent pixels between two emojis
+    pub padding: u32,
+    /// Write the individual images in addition to the atlases
+    pub files: bool,
+}
+
+/// Settings for [`crate::fontbuild`].
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FontSettings {
+    pub format: FontFormat,
+    /// Family name, defaults to `emojicrunch {vendor}`
+    pub family: Option<String>,
+    /// Heights of the sizes to use as strikes, defaults to all sizes
+    pub sizes: Vec<u32>,
+}
+
+/// The color bitmap tables of a generated font.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FontFormat {
+    /// CBDT/CBLC (FreeType, Android, Chrome)
+    #[default]
+    Cbdt,
+    /// sbix (Apple platforms)
+    Sbix,
+}
+
+/// How the strike (bitmap size) of sbix and CBDT fonts is chosen for each output size.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StrikeSelection {
+    /// The smallest strike that's at least as large as the size (or the largest strike)
+    #[default]
+    Nearest,
+    /// Always the largest strike
+    Largest,
+}
+
+impl Config {
+    /// Reads a config file. Files ending in `.json` are parsed as JSON, everything else as TOML.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let data = fs_err::read_to_string(path)?;
+        let mut config: Self = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&data)?
+        } else {
+            toml::from_str(&data)?
+        };
+        if config.targets.is_empty() {
+            config.targets.push(Target::default());
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks the settings of all targets (see [`Target::validate`]).
+    pub fn validate(&self) -> Result<(), Error> {
+        self.targets.iter().try_for_each(Target::validate)
+    }
+
+    /// The font `vendor` is rendered from, if any.
+    ///
+    /// `apple_font` is used for Apple unless `vendor_fonts` overrides it.
+    pub fn font_for(&self, vendor: &Vendor) -> Option<&Path> {
+        match self.vendor_fonts.get(vendor) {
+            Some(path) => Some(path),
+            None if *vendor == Vendor::Apple => self.apple_font.as_deref(),
+            None => None,
+        }
+    }
+
+    /// The vendors tried if an emoji is missing from `vendor` (without `vendor` itself).
+    pub fn fallbacks_for(&self, vendor: &Vendor) -> Vec<Vendor> {
+        let mut chain: Vec<Vendor> = Vec::new();
+        for it in self.fallbacks.get(vendor).into_iter().flatten() {
+            if it != vendor && !chain.contains(it) {
+                chain.push(it.clone());
+            }
+        }
+        chain
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            emoji_data_root: default_emoji_data_root(),
+            apple_font: None,
+            vendor_fonts: HashMap::new(),
+            font_index: 0,
+            font_strike: StrikeSelection::default(),
+            fallbacks: HashMap::new(),
+            targets: vec![Target::default()],
+            failures: FailurePolicy::default(),
+        }
+    }
+}
+
+impl Default for Target {
+    fn default() -> Self {
+        Self {
+            vendors: default_vendors(),
+            sizes: default_sizes(),
+            densities: None,
+            formats: default_formats(),
+            output_dir: default_output_dir(),
+            layout: Template::default(),
+            vendor_layouts: HashMap::new(),
+            cache: true,
+            atlas: None,
+            css: false,
+            font: None,
+            filter: FilterSettings::default(),
+            settings: Settings::default(),
+            unknown: BTreeMap::new(),
+        }
+    }
+}
+
+impl Target {
+    /// Checks for unknown keys and settings that can't be built.
+    ///
+    /// Formats sharing an extension (e.g. oxipng and zopflipng) would write to the same files, as
+    /// would layouts missing a placeholder (see [`Template::check_distinct`]), and the padding
+    /// has to leave space for the emoji at the smallest size.
+    pub fn validate(&self) -> Result<(), Error> {
+        if !self.unknown.is_empty() {
+            let keys: Vec<_> = self.unknown.keys().map(|it| format!("`{it}`")).collect();
+            return Err(Error::Config(format!(
+                "unknown keys in target: {}",
+                keys.join(", ")
+            )));
+        }
+        for (i, format) in self.formats.iter().enumerate() {
+            if let Some(other) = self.formats[..i]
+                .iter()
+                .find(|it| it.extension() == format.extension())
+            {
+                let name = |it: &Format| clap::ValueEnum::to_possible_value(it).unwrap();
+                return Err(Error::Config(format!(
+                    "{} and {} both write .{} files, only one of them can be used in a target",
+                    name(other).get_name(),
+                    name(format).get_name(),
+                    format.extension()
+                )));
+            }
+        }
+
+        let sizes = self.output_sizes().map_err(Error::Config)?;
+        for layout in std::iter::once(&self.layout).chain(self.vendor_layouts.values()) {
+            let vendors = self
+                .vendors
+                .iter()
+                .filter(|it| self.layout_for(it) == layout)
+                .count();
+            layout.check_distinct(sizes.len(), self.formats.len(), vendors)?;
+        }
+        if let Padding::Pixels(padding) = self.settings.fit.padding {
+            // the width of height-only sizes depends on the source
+            let smallest = sizes
+                .iter()
+                .map(|it| it.width.unwrap_or(it.height).min(it.height))
+                .min();
+            if let Some(smallest) = smallest.filter(|&it| padding.saturating_mul(2) >= it) {
+                return Err(Error::Config(format!(
+                    "a padding of {padding}px leaves no space for the emoji at a size of \
+                     {smallest}px"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// The layout used for `vendor`.
+    pub fn layout_for(&self, vendor: &Vendor) -> &Template {
+        self.vendor_layouts.get(vendor).unwrap_or(&self.layout)
+    }
+
+    /// The sizes to render, either [`Self::sizes`] or the ones derived from the densities.
+    pub fn output_sizes(&self) -> Result<Vec<Size>, String> {
+        let mut sizes = match self.densities {
+            Some(ref densities) => densities.sizes()?,
+            None => self.sizes.clone(),
+        };
+        sizes.dedup();
+        Ok(sizes)
+    }
+
+    /// The atlas settings, if atlases are built (explicitly or for the stylesheets).
+    pub fn atlas_settings(&self) -> Option<AtlasSettings> {
+        self.atlas
+            .clone()
+            .or_else(|| self.css.then(AtlasSettings::default))
+    }
+}
+
+impl FilterSettings {
+    /// Checks if no condition is set, so all emojis are built.
+    pub fn is_empty(&self) -> bool {
+        self.since.is_none()
+            && self.categories.is_empty()
+            && !self.exclude_skin_tones
+            && self.only.is_empty()
+    }
+}
+
+impl DensitySettings {
+    /// The base size scaled by each density.
+    pub fn sizes(&self) -> Result<Vec<Size>, String> {
+        self.scales
+            .iter()
+            .map(|&scale| Ok(self.base.scaled(Density::new(scale, self.naming)?)))
+            .collect()
+    }
+}
+
+impl Default for DensitySettings {
+    fn default() -> Self {
+        Self {
+            base: Size::square(22),
+            scales: vec![1.0, 1.5, 2.0, 3.0, 4.0],
+            naming: DensityNaming::default(),
+        }
+    }
+}
+
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Oxipng | Format::Zopflipng => "png",
+            Format::Webp => "webp",
+            Format::Avif => "avif",
+            Format::Jxl => "jxl",
+        }
+    }
+
+    pub fn optimizer(self) -> Box<dyn Optimizer> {
+        match self {
+            Format::Oxipng => Box::new(Oxipng),
+            Format::Zopflipng => Box::new(Zopflipng),
+            Format::Webp => Box::new(Webp),
+            Format::Avif => Box::new(Avif),
+            Format::Jxl => Box::new(Jxl),
+        }
+    }
+}
+
+impl FontFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FontFormat::Cbdt => "cbdt",
+            FontFormat::Sbix => "sbix",
+        }
+    }
+}
+
+impl Default for Padding {
+    fn default() -> Self {
+        Self::Pixels(0)
+    }
+}
+
+impl std::str::FromStr for Padding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            || format!("invalid padding '{s}', expected pixels (e.g. 4) or percent (e.g. 10%)");
+        if let Some(percent) = s.strip_suffix('%') {
+            let percent: f32 = percent.trim().parse().map_err(|_| invalid())?;
+            if !(0.0..50.0).contains(&percent) {
+                return Err(invalid());
+            }
+            return Ok(Self::Percent(percent));
+        }
+        let pixels = s.strip_suffix("px").unwrap_or(s).trim();
+        pixels.parse().map(Self::Pixels).map_err(|_| invalid())
+    }
+}
+
+impl TryFrom<PaddingValue> for Padding {
+    type Error = String;
+
+    fn try_from(value: PaddingValue) -> Result<Self, Self::Error> {
+        match value {
+            PaddingValue::Pixels(px) => Ok(Self::Pixels(px)),
+            PaddingValue::Text(text) => text.parse(),
+        }
+    }
+}
+
+impl Default for AtlasSettings {
+    fn default() -> Self {
+        Self {
+            max_size: 4096,
+            padding: 0,
+            files: true,
+        }
+    }
+}
+
+impl Default for OxipngSettings {
+    fn default() -> Self {
+        Self { level: 6 }
+    }
+}
+
+impl Default for ZopfliSettings {
+    fn default() -> Self {
+        Self {
+            iterations: 15,
+            iterations_large: 11,
+            lossy_transparent: true,
+        }
+    }
+}
+
+impl Default for WebpSettings {
+    fn default() -> Self {
+        Self {
+            lossless: true,
+            quality: 100.0,
+            near_lossless: 100,
+            alpha_quality: 100,
+            alpha_filtering: 1,
+            exact: false,
+            method: 6,
+        }
+    }
+}
+
+impl Default for AvifSettings {
+    fn default() -> Self {
+        Self {
+            lossless: false,
+            quality: 80.0,
+            alpha_quality: 90.0,
+            speed: 4,
+        }
+    }
+}
+
+impl Default for JxlSettings {
+    fn default() -> Self {
+        Self {
+            lossless: true,
+            distance: 1.0,
+            effort: 7,
+        }
+    }
+}
+
+fn default_emoji_data_root() -> PathBuf {
+    PathBuf::from("emoji-data")
+}
+
+fn default_vendors() -> Vec<Vendor> {
+    Vendor::ALL.to_vec()
+}
+
+fn default_sizes() -> Vec<Size> {
+    vec![Size::square(22), Size::square(22 * 2), Size::square(22 * 4)]
+}
+
+fn default_formats() -> Vec<Format> {
+    vec![Format::default()]
+}
+
+fn default_output_dir() -> PathBuf {
+    PathBuf::from("build")
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Accepts a single value or a list of values.
+fn one_or_many<'de, D, T>(d: D) -> Result<Vec<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::Deserialize<'de>,
+{
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany<T> {
+        One(T),
+        Many(Vec<T>),
+    }
+
+    Ok(
+        match <OneOrMany<T> as serde::Deserialize>::deserialize(d)? {
+            OneOrMany::One(it) => vec![it],
+            OneOrMany::Many(it) => it,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_padding() {
+        assert_eq!("10%".parse(), Ok(Padding::Percent(10.0)));
+        assert_eq!("2.5%".parse(), Ok(Padding::Percent(2.5)));
+        assert_eq!("4px".parse(), Ok(Padding::Pixels(4)));
+        assert_eq!("4".parse(), Ok(Padding::Pixels(4)));
+        assert_eq!("0".parse(), Ok(Padding::Pixels(0)));
+        for invalid in ["", "%", "50%", "-1%", "-1", "4.5", "4pt", "ten"] {
+            assert!(invalid.parse::<Padding>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn deserialize_padding() {
+        let fit: FitSettings = toml::from_str("padding = 2").unwrap();
+        assert_eq!(fit.padding, Padding::Pixels(2));
+        let fit: FitSettings = toml::from_str(r#"padding = "10%""#).unwrap();
+        assert_eq!(fit.padding, Padding::Percent(10.0));
+        assert!(toml::from_str::<FitSettings>(r#"padding = "60%""#).is_err());
+    }
+
+    #[test]
+    fn validate_padding() {
+        let mut target = Target::default();
+        target.settings.fit.padding = Padding::Pixels(10);
+        assert!(target.validate().is_ok());
+        // the smallest default size is 22
+        target.settings.fit.padding = Padding::Pixels(11);
+        assert!(matches!(target.validate(), Err(Error::Config(_))));
+        target.sizes = vec!["44x23".parse().unwrap()];
+        assert!(target.validate().is_ok());
+    }
+}
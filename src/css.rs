@@ -0,0 +1,136 @@
+use std::fmt::Write;
+
+use crate::{Error, Options, atlas::Index, manifest::OutputFile};
+
+/// Writes a stylesheet for every atlas size to `{vendor}/emoji-{size}.css`.
+///
+/// Each emoji gets two classes: `emoji-{short_name}` and `emoji-u{unified}` (e.g. `emoji-+1` and
+/// `emoji-u1f44d`), which are used together with the `emoji` class:
+///
+/// ```html
+/// <i class="emoji emoji-+1"></i>
+/// ```
+///
+/// Larger sizes that are a multiple of a size are added as higher densities with `image-set()`
+/// (e.g. 44px and 88px are `2x` and `4x` of 22px). This requires the atlases to have no padding.
+/// For sizes with only a height, the width is set for every emoji.
+pub fn write(options: &Options, indices: &[Index], padding: u32) -> Result<(), Error> {
+    let dir = options.outputs()[0].directories.provider_dir();
+    for index in indices {
+        let densities: Vec<(u32, &Index)> = indices
+            .iter()
+            .filter(|it| padding == 0 || it.size == index.size)
+            .filter_map(|it| Some((it.size.multiple_of(&index.size)?, it)))
+            .collect();
+        let css = stylesheet(index, &densities);
+        fs_err::write(dir.join(format!("emoji-{}.css", index.size)), css)?;
+    }
+    Ok(())
+}
+
+fn stylesheet(index: &Index, densities: &[(u32, &Index)]) -> String {
+    let mut out = String::from("/* Generated by emojicrunch */\n\n");
+    // without a fixed width, every emoji has its own
+    let width = match index.size.width {
+        Some(width) => format!("\n  width: {width}px;"),
+        None => String::new(),
+    };
+    writeln!(
+        out,
+        ".emoji {{\n  display: inline-block;{width}\n  height: {}px;\n  \
+         background-repeat: no-repeat;\n}}\n",
+        index.size.height
+    )
+    .unwrap();
+
+    for (page_index, page) in index.pages.iter().enumerate() {
+        let selectors: Vec<_> = index
+            .emojis
+            .iter()
+            .filter(|(_, rect)| rect.page == page_index)
+            .map(|(unified, rect)| selectors(unified, &rect.short_name))
+            .collect();
+        if selectors.is_empty() {
+            continue;
+        }
+        let Some(fallback) = page.files.first() else {
+            continue;
+        };
+
+        let mut images = Vec::new();
+        for (density, other) in densities {
+            let Some(other) = other.pages.get(page_index) else {
+                continue;
+            };
+            for file in &other.files {
+                images.push(format!(
+                    "url(\"{}\") type(\"{}\") {density}x",
+                    file_name(file),
+                    mime_type(file)
+                ));
+            }
+        }
+
+        writeln!(
+            out,
+            "{} {{\n  background-image: url(\"{}\");\n  background-image: image-set({});\n  \
+             background-size: {}px {}px;\n}}\n",
+            selectors.join(",\n"),
+            file_name(fallback),
+            images.join(", "),
+            page.width,
+            page.height
+        )
+        .unwrap();
+    }
+
+    for (unified, rect) in &index.emojis {
+        let width = match index.size.width {
+            Some(_) => String::new(),
+            None => format!(" width: {}px;", rect.width),
+        };
+        writeln!(
+            out,
+            "{} {{ background-position: -{}px -{}px;{width} }}",
+            selectors(unified, &rect.short_name),
+            rect.x,
+            rect.y
+        )
+        .unwrap();
+    }
+    out
+}
+
+fn selectors(unified: &str, short_name: &str) -> String {
+    format!(".emoji-{}, .emoji-u{unified}", escape(short_name))
+}
+
+/// Escapes characters that aren't allowed in CSS identifiers (e.g. `+`).
+fn escape(ident: &str) -> String {
+    let mut out = String::with_capacity(ident.len());
+    for c in ident.chars() {
+        if !(c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// The stylesheet is placed next to the atlases.
+fn file_name(file: &OutputFile) -> String {
+    file.path
+        .file_name()
+        .map(|it| it.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn mime_type(file: &OutputFile) -> &'static str {
+    match file.format.as_str() {
+        "png" => "image/png",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "jxl" => "image/jxl",
+        _ => "application/octet-stream",
+    }
+}
@@ -1,37 +1,353 @@
-use std::path::{Path, PathBuf};
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
-use crate::EmojiImage;
+use crate::{EmojiImage, Error, Size};
 
+/// Decides where the outputs of a provider are written to.
 pub struct Directories {
-    base_dir: PathBuf,
+    root: PathBuf,
+    provider: String,
     extension: &'static str,
+    template: Template,
+}
+
+/// A path template like `{vendor}/{size}/{unified}.{ext}`.
+///
+/// Supported placeholders:
+///
+/// - `{vendor}`: name of the provider/vendor
+/// - `{size}`: the size in pixels (e.g. `22`, `28x22`, or `x22`)
+/// - `{density}`: the density of the size (e.g. `@2x` or `xhdpi`), empty for iOS' 1x and sizes
+///   without a density
+/// - `{unified}`: unified codepoints (e.g. `1f44d-1f3fb`)
+/// - `{non_qualified}`: non-qualified codepoints, falls back to `{unified}`
+/// - `{short_name}`: short name (e.g. `+1`), suffixed with `-{skin_tone}` for skin variations
+/// - `{skin_tone}`: skin tone codepoints of a skin variation or an empty string
+/// - `{ext}`: the file extension (e.g. `png`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    source: String,
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Vendor,
+    Size,
+    Density,
+    Unified,
+    NonQualified,
+    ShortName,
+    SkinTone,
+    Ext,
 }
 
 impl Directories {
-    pub fn new(base_dir: PathBuf, extension: &'static str) -> Self {
+    pub fn new(
+        root: impl AsRef<Path>,
+        provider: impl Into<String>,
+        extension: &'static str,
+        template: Template,
+    ) -> Self {
         Self {
-            base_dir,
+            root: root.as_ref().to_owned(),
+            provider: provider.into(),
             extension,
+            template,
         }
     }
 
+    /// Uses the default layout (`{vendor}/{size}/{unified}.{ext}`).
     pub fn for_provider(
         base_dir: impl AsRef<Path>,
-        provider: impl AsRef<Path>,
+        provider: impl Into<String>,
         extension: &'static str,
     ) -> Self {
-        Self::new(base_dir.as_ref().join(provider.as_ref()), extension)
+        Self::new(base_dir, provider, extension, Template::default())
+    }
+
+    /// The output directory all paths are relative to.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The name of the provider/vendor.
+    pub fn provider(&self) -> &str {
+        &self.provider
+    }
+
+    /// The directory containing metadata of the provider (cache and manifest).
+    pub fn provider_dir(&self) -> PathBuf {
+        self.root.join(&self.provider)
+    }
+
+    pub fn extension(&self) -> &'static str {
+        self.extension
+    }
+
+    pub fn template(&self) -> &Template {
+        &self.template
+    }
+
+    pub fn for_emoji(&self, size: Size, emoji: &EmojiImage) -> PathBuf {
+        self.root.join(self.relative_emoji_path(size, emoji))
+    }
+
+    /// Path of an emoji relative to [`Self::root`].
+    pub fn relative_emoji_path(&self, size: Size, emoji: &EmojiImage) -> PathBuf {
+        PathBuf::from(
+            self.template
+                .render(&self.provider, size, emoji, self.extension),
+        )
+    }
+}
+
+impl Template {
+    pub fn as_str(&self) -> &str {
+        &self.source
     }
 
-    pub fn create_sizes(&self, sizes: &[u32]) -> std::io::Result<()> {
-        for size in sizes {
-            fs_err::create_dir_all(self.base_dir.join(size.to_string()))?;
+    /// Checks that the outputs of a target get distinct paths.
+    ///
+    /// Without `{size}` or `{density}`, `{ext}`, or `{vendor}`, the outputs for multiple sizes,
+    /// formats, or vendors would overwrite each other.
+    pub fn check_distinct(
+        &self,
+        sizes: usize,
+        formats: usize,
+        vendors: usize,
+    ) -> Result<(), Error> {
+        let contains = |f: fn(&Part) -> bool| self.parts.iter().any(f);
+        let missing = if sizes > 1 && !contains(|it| matches!(it, Part::Size | Part::Density)) {
+            Some(("{size} or {density}", "sizes"))
+        } else if formats > 1 && !contains(|it| matches!(it, Part::Ext)) {
+            Some(("{ext}", "formats"))
+        } else if vendors > 1 && !contains(|it| matches!(it, Part::Vendor)) {
+            Some(("{vendor}", "vendors"))
+        } else {
+            None
+        };
+        match missing {
+            Some((placeholder, outputs)) => Err(Error::Template(format!(
+                "'{}' must contain {placeholder} to write multiple {outputs}",
+                self.source
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    fn render(&self, vendor: &str, size: Size, emoji: &EmojiImage, extension: &str) -> String {
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(s) => out.push_str(s),
+                Part::Vendor => out.push_str(vendor),
+                Part::Size => write!(out, "{size}").unwrap(),
+                Part::Density => {
+                    if let Some(density) = size.density {
+                        out.push_str(&density.suffix());
+                    }
+                }
+                Part::Unified => out.push_str(emoji.unified),
+                Part::NonQualified => out.push_str(emoji.non_qualified.unwrap_or(emoji.unified)),
+                Part::ShortName => out.push_str(&emoji.name()),
+                Part::SkinTone => {
+                    out.push_str(&emoji.skin_tone.unwrap_or_default().to_ascii_lowercase())
+                }
+                Part::Ext => out.push_str(extension),
+            }
+        }
+        out
+    }
+}
+
+impl Default for Template {
+    fn default() -> Self {
+        "{vendor}/{size}/{unified}.{ext}".parse().unwrap()
+    }
+}
+
+impl FromStr for Template {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut rest = s;
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                parts.push(Part::Literal(rest[..start].to_owned()));
+            }
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| Error::Template(format!("unclosed placeholder in '{s}'")))?;
+            let part = match &rest[start + 1..start + end] {
+                "vendor" => Part::Vendor,
+                "size" => Part::Size,
+                "density" => Part::Density,
+                "unified" => Part::Unified,
+                "non_qualified" => Part::NonQualified,
+                "short_name" => Part::ShortName,
+                "skin_tone" => Part::SkinTone,
+                "ext" => Part::Ext,
+                other => {
+                    return Err(Error::Template(format!(
+                        "unknown placeholder '{{{other}}}' in '{s}'"
+                    )));
+                }
+            };
+            parts.push(part);
+            rest = &rest[start + end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_owned()));
+        }
+
+        if !parts
+            .iter()
+            .any(|it| matches!(it, Part::Unified | Part::NonQualified | Part::ShortName))
+        {
+            return Err(Error::Template(format!(
+                "'{s}' must contain one of {{unified}}, {{non_qualified}}, or {{short_name}}"
+            )));
+        }
+
+        Ok(Self {
+            source: s.to_owned(),
+            parts,
+        })
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Template {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl serde::Serialize for Template {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emoji::{HasImg, Version};
+
+    fn emoji(skin_tone: Option<&'static str>) -> EmojiImage<'static> {
+        EmojiImage {
+            unified: if skin_tone.is_some() {
+                "1f44d-1f3fb"
+            } else {
+                "1f44d"
+            },
+            non_qualified: None,
+            short_name: "+1",
+            skin_tone,
+            base_unified: "1f44d",
+            category: "People & Body",
+            subcategory: "hand-fingers-closed",
+            added_in: Version { major: 0, minor: 6 },
+            sort_order: 1,
+            has_img: HasImg::default(),
+        }
+    }
+
+    fn render(template: &str, size: Size, emoji: &EmojiImage) -> String {
+        let template = template.parse().unwrap();
+        let directories = Directories::new("build", "twitter", "png", template);
+        directories
+            .relative_emoji_path(size, emoji)
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn render_placeholders() {
+        let size = Size::square(22);
+        assert_eq!(
+            render("{vendor}/{size}/{unified}.{ext}", size, &emoji(None)),
+            "twitter/22/1f44d.png"
+        );
+        assert_eq!(
+            render("{size}/{short_name}.{ext}", size, &emoji(Some("1F3FB"))),
+            "22/+1-1f3fb.png"
+        );
+        assert_eq!(
+            render("{non_qualified}-{skin_tone}", size, &emoji(Some("1f3fb"))),
+            "1f44d-1f3fb-1f3fb"
+        );
+        assert_eq!(render("{unified}{density}", size, &emoji(None)), "1f44d");
+        let density = crate::size::Density::new(2.0, crate::size::DensityNaming::Ios).unwrap();
+        assert_eq!(
+            render("{unified}{density}", size.scaled(density), &emoji(None)),
+            "1f44d@2x"
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        for invalid in [
+            "{vendor}/{size}/{unified",
+            "{vendor}/{unknown}/{unified}",
+            "{}/{unified}",
+            "{vendor}/{size}.{ext}",
+            "",
+        ] {
+            assert!(
+                matches!(invalid.parse::<Template>(), Err(Error::Template(_))),
+                "{invalid}"
+            );
+        }
+
+        let template = |s: &str| s.parse::<Template>().unwrap();
+        for (invalid, sizes, formats, vendors) in [
+            ("{vendor}/{unified}.{ext}", 2, 1, 1),
+            ("{vendor}/{size}/{unified}.png", 1, 2, 1),
+            ("{size}/{unified}.{ext}", 1, 1, 2),
+            ("{unified}.png", 3, 2, 2),
+        ] {
+            assert!(
+                matches!(
+                    template(invalid).check_distinct(sizes, formats, vendors),
+                    Err(Error::Template(_))
+                ),
+                "{invalid}"
+            );
+        }
+        for (valid, sizes, formats, vendors) in [
+            ("{vendor}/{size}/{unified}.{ext}", 3, 2, 2),
+            ("{vendor}/{unified}{density}.{ext}", 3, 2, 2),
+            ("{unified}.png", 1, 1, 1),
+        ] {
+            assert!(
+                template(valid)
+                    .check_distinct(sizes, formats, vendors)
+                    .is_ok(),
+                "{valid}"
+            );
         }
-        Ok(())
     }
 
-    pub fn for_emoji(&self, size: u32, emoji: &EmojiImage) -> PathBuf {
-        self.base_dir
-            .join(format!("{size}/{}.{}", emoji.unified, self.extension))
+    #[test]
+    fn literals() {
+        let template: Template = "emoji_u{unified}.png".parse().unwrap();
+        assert_eq!(template.as_str(), "emoji_u{unified}.png");
+        assert_eq!(
+            render("emoji_u{unified}}.png", Size::square(22), &emoji(None)),
+            "emoji_u1f44d}.png"
+        );
     }
 }
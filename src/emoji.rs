@@ -0,0 +1,388 @@
+use std::{collections::HashMap, fmt, iter, path::Path, str::FromStr};
+
+use crate::{Error, Vendor, config::FilterSettings};
+
+/// An entry of emoji-data's `emoji.json`.
+#[derive(serde::Deserialize)]
+pub struct Emoji {
+    pub short_name: String,
+    #[serde(deserialize_with = "deser_lower")]
+    pub unified: String,
+    #[serde(deserialize_with = "deser_lower_opt")]
+    pub non_qualified: Option<String>,
+    /// e.g. `Smileys & Emotion`
+    pub category: String,
+    /// e.g. `face-smiling`
+    pub subcategory: String,
+    /// The Emoji version this emoji was introduced in
+    pub added_in: Version,
+    /// Position in the emoji picker
+    pub sort_order: u32,
+    #[serde(flatten)]
+    pub has_img: HasImg,
+    #[serde(default)]
+    pub skin_variations: HashMap<String, SkinVariation>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct SkinVariation {
+    #[serde(deserialize_with = "deser_lower_opt")]
+    pub non_qualified: Option<String>,
+    #[serde(deserialize_with = "deser_lower")]
+    pub unified: String,
+    /// Skin variations can be newer than their emoji, defaults to [`Emoji::added_in`]
+    #[serde(default)]
+    pub added_in: Option<Version>,
+    #[serde(flatten)]
+    pub has_img: HasImg,
+}
+
+/// The `has_img_{vendor}` flags - whether a vendor has an image for an emoji.
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize)]
+pub struct HasImg {
+    #[serde(default, rename = "has_img_apple")]
+    pub apple: bool,
+    #[serde(default, rename = "has_img_twitter")]
+    pub twitter: bool,
+    #[serde(default, rename = "has_img_google")]
+    pub google: bool,
+    #[serde(default, rename = "has_img_facebook")]
+    pub facebook: bool,
+}
+
+/// An Emoji version like `15.0` (or `0.6` for the emojis from before Emoji 1.0).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Version {
+    pub major: u16,
+    pub minor: u16,
+}
+
+/// A single image to produce - either an emoji or one of its skin variations.
+#[derive(Clone, Copy)]
+pub struct EmojiImage<'a> {
+    pub unified: &'a str,
+    pub non_qualified: Option<&'a str>,
+    pub short_name: &'a str,
+    /// The skin tone(s) of a skin variation (e.g. `1f3fb` or `1f3fb-1f3fc`)
+    pub skin_tone: Option<&'a str>,
+    /// The emoji this image belongs to (the same as the image unless it's a skin variation)
+    pub base_unified: &'a str,
+    pub category: &'a str,
+    pub subcategory: &'a str,
+    pub added_in: Version,
+    pub sort_order: u32,
+    pub has_img: HasImg,
+}
+
+impl EmojiImage<'_> {
+    /// The short name, suffixed with `-{skin_tone}` for skin variations (e.g. `+1-1f3fb`).
+    pub fn name(&self) -> String {
+        match self.skin_tone {
+            Some(tone) => format!("{}-{}", self.short_name, tone.to_ascii_lowercase()),
+            None => self.short_name.to_owned(),
+        }
+    }
+
+    /// Checks if this image passes all conditions of `filter`.
+    ///
+    /// `only` matches the image itself or the emoji of a skin variation. `categories` match the
+    /// category or subcategory (case-insensitive).
+    pub fn matches(&self, filter: &FilterSettings) -> bool {
+        if filter.exclude_skin_tones && self.skin_tone.is_some() {
+            return false;
+        }
+        if filter.since.is_some_and(|since| self.added_in < since) {
+            return false;
+        }
+        if !filter.categories.is_empty()
+            && !filter.categories.iter().any(|it| {
+                it.eq_ignore_ascii_case(self.category) || it.eq_ignore_ascii_case(self.subcategory)
+            })
+        {
+            return false;
+        }
+        filter.only.is_empty()
+            || filter.only.iter().any(|it| {
+                it.eq_ignore_ascii_case(self.unified) || it.eq_ignore_ascii_case(self.base_unified)
+            })
+    }
+}
+
+impl HasImg {
+    pub fn has(&self, vendor: &Vendor) -> bool {
+        match vendor {
+            Vendor::Apple => self.apple,
+            Vendor::Twitter => self.twitter,
+            Vendor::Google => self.google,
+            Vendor::Facebook => self.facebook,
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl FromStr for Version {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid version '{s}', expected e.g. 15.0");
+        let (major, minor) = s.split_once('.').unwrap_or((s, "0"));
+        Ok(Self {
+            major: major.parse().map_err(|_| invalid())?,
+            minor: minor.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Version {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum VersionValue {
+            Number(f64),
+            Text(String),
+        }
+
+        match VersionValue::deserialize(deserializer)? {
+            // `15.0` in TOML is a float
+            VersionValue::Number(n) => format!("{n:.1}").parse(),
+            VersionValue::Text(s) => s.parse(),
+        }
+        .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Reads `emoji.json` from the root of an emoji-data checkout.
+pub fn read_catalog(emoji_data_root: impl AsRef<Path>) -> Result<Vec<Emoji>, Error> {
+    let data = fs_err::read(emoji_data_root.as_ref().join("emoji.json"))?;
+    serde_json::from_slice(&data).map_err(Into::into)
+}
+
+/// Flattens the emojis and their skin variations into the list of images to produce.
+pub fn images(emojis: &[Emoji]) -> Vec<EmojiImage<'_>> {
+    emojis
+        .iter()
+        .flat_map(|it| {
+            iter::once(EmojiImage {
+                short_name: &it.short_name,
+                non_qualified: it.non_qualified.as_deref(),
+                unified: &it.unified,
+                skin_tone: None,
+                base_unified: &it.unified,
+                category: &it.category,
+                subcategory: &it.subcategory,
+                added_in: it.added_in,
+                sort_order: it.sort_order,
+                has_img: it.has_img,
+            })
+            .chain(it.skin_variations.iter().map(|(tone, v)| EmojiImage {
+                short_name: &it.short_name,
+                non_qualified: v.non_qualified.as_deref(),
+                unified: &v.unified,
+                skin_tone: Some(tone.as_str()),
+                base_unified: &it.unified,
+                category: &it.category,
+                subcategory: &it.subcategory,
+                added_in: v.added_in.unwrap_or(it.added_in),
+                sort_order: it.sort_order,
+                has_img: v.has_img,
+            }))
+        })
+        .collect()
+}
+
+fn deser_lower<'de, D>(d: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    struct Visitor;
+    impl serde::de::Visitor<'_> for Visitor {
+        type Value = String;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "a string")
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+        {
+            Ok(v.to_ascii_lowercase())
+        }
+    }
+
+    d.deserialize_str(Visitor)
+}
+
+fn deser_lower_opt<'de, D>(d: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    struct Visitor;
+    impl<'deinner> serde::de::Visitor<'deinner> for Visitor {
+        type Value = Option<String>;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "a string")
+        }
+
+        fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: serde::Deserializer<'deinner>,
+        {
+            deserializer.deserialize_str(Visitor)
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+        {
+            Ok(Some(v.to_ascii_lowercase()))
+        }
+
+        fn visit_none<E>(self) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+        {
+            Ok(None)
+        }
+    }
+
+    d.deserialize_option(Visitor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(major: u16, minor: u16) -> Version {
+        Version { major, minor }
+    }
+
+    #[test]
+    fn parse_version() {
+        assert_eq!("15.0".parse(), Ok(version(15, 0)));
+        assert_eq!("0.6".parse(), Ok(version(0, 6)));
+        assert_eq!("15".parse(), Ok(version(15, 0)));
+        for invalid in ["", ".", "15.", ".1", "a.b", "1.2.3", "-1.0"] {
+            assert!(invalid.parse::<Version>().is_err(), "{invalid}");
+        }
+        assert!(version(0, 6) < version(1, 0));
+        assert!(version(13, 1) < version(15, 0));
+        assert_eq!(version(15, 1).to_string(), "15.1");
+    }
+
+    #[test]
+    fn deserialize_version() {
+        let filter: FilterSettings = toml::from_str("since = 15.0").unwrap();
+        assert_eq!(filter.since, Some(version(15, 0)));
+        let filter: FilterSettings = toml::from_str("since = 13.1").unwrap();
+        assert_eq!(filter.since, Some(version(13, 1)));
+        let filter: FilterSettings = toml::from_str(r#"since = "0.6""#).unwrap();
+        assert_eq!(filter.since, Some(version(0, 6)));
+        assert!(toml::from_str::<FilterSettings>(r#"since = "new""#).is_err());
+    }
+
+    fn catalog() -> Vec<Emoji> {
+        serde_json::from_str(
+            r#"[
+                {
+                    "short_name": "+1",
+                    "unified": "1F44D",
+                    "non_qualified": null,
+                    "category": "People & Body",
+                    "subcategory": "hand-fingers-closed",
+                    "added_in": "0.6",
+                    "sort_order": 193,
+                    "has_img_apple": true,
+                    "has_img_facebook": false,
+                    "skin_variations": {
+                        "1F3FB": {
+                            "unified": "1F44D-1F3FB",
+                            "non_qualified": null,
+                            "added_in": "1.0",
+                            "has_img_apple": true
+                        }
+                    }
+                },
+                {
+                    "short_name": "flag-de",
+                    "unified": "1F1E9-1F1EA",
+                    "non_qualified": null,
+                    "category": "Flags",
+                    "subcategory": "country-flag",
+                    "added_in": "2.0",
+                    "sort_order": 1700
+                }
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    fn matching(filter: &FilterSettings) -> Vec<String> {
+        let catalog = catalog();
+        images(&catalog)
+            .iter()
+            .filter(|it| it.matches(filter))
+            .map(|it| it.unified.to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn read_catalog_fields() {
+        let catalog = catalog();
+        let images = images(&catalog);
+        assert_eq!(images.len(), 3);
+        assert_eq!(images[0].unified, "1f44d");
+        assert_eq!(images[0].category, "People & Body");
+        assert!(images[0].has_img.has(&Vendor::Apple));
+        assert!(!images[0].has_img.has(&Vendor::Facebook));
+        assert_eq!(images[1].unified, "1f44d-1f3fb");
+        assert_eq!(images[1].base_unified, "1f44d");
+        assert_eq!(images[1].added_in, version(1, 0));
+        assert!(!images[2].has_img.has(&Vendor::Twitter));
+    }
+
+    #[test]
+    fn filter() {
+        let all = FilterSettings::default();
+        assert!(all.is_empty());
+        assert_eq!(matching(&all).len(), 3);
+
+        let since = FilterSettings {
+            since: Some(version(1, 0)),
+            ..Default::default()
+        };
+        assert_eq!(matching(&since), ["1f44d-1f3fb", "1f1e9-1f1ea"]);
+
+        let flags = FilterSettings {
+            categories: vec!["flags".to_owned()],
+            ..Default::default()
+        };
+        assert_eq!(matching(&flags), ["1f1e9-1f1ea"]);
+        let subcategory = FilterSettings {
+            categories: vec!["hand-fingers-closed".to_owned()],
+            exclude_skin_tones: true,
+            ..Default::default()
+        };
+        assert_eq!(matching(&subcategory), ["1f44d"]);
+
+        let only = FilterSettings {
+            only: vec!["1F44D".to_owned()],
+            ..Default::default()
+        };
+        assert_eq!(matching(&only), ["1f44d", "1f44d-1f3fb"]);
+        let only_variation = FilterSettings {
+            only: vec!["1f44d-1f3fb".to_owned()],
+            ..Default::default()
+        };
+        assert_eq!(matching(&only_variation), ["1f44d-1f3fb"]);
+    }
+}
@@ -2,6 +2,7 @@ use std::{io::Cursor, path::Path};
 
 use crate::{
     EmojiImage,
+    manifest::OutputFile,
     provider::{ImageProvider, Provider},
 };
 
@@ -17,16 +18,7 @@ impl<'a> EmojiDataPngs<'a> {
 
 impl ImageProvider for EmojiDataPngs<'_> {
     fn read_image(&self, emoji: &EmojiImage) -> Result<image::DynamicImage, crate::Error> {
-        let mut path = self.dir.join(emoji.unified);
-        path.set_extension("png");
-        let data = fs_err::read(path).or_else(|e| match emoji.non_qualified {
-            Some(nq) => {
-                let mut path = self.dir.join(nq);
-                path.set_extension("png");
-                fs_err::read(path)
-            }
-            None => Err(e),
-        })?;
+        let data = self.read_source(emoji)?;
         let mut reader = image::ImageReader::new(Cursor::new(data));
         reader.set_format(image::ImageFormat::Png);
         reader.decode().map_err(Into::into)
@@ -34,12 +26,27 @@ impl ImageProvider for EmojiDataPngs<'_> {
 }
 
 impl Provider for EmojiDataPngs<'_> {
+    fn read_source(&self, emoji: &EmojiImage) -> Result<Vec<u8>, crate::Error> {
+        let mut path = self.dir.join(emoji.unified);
+        path.set_extension("png");
+        fs_err::read(path)
+            .or_else(|e| match emoji.non_qualified {
+                Some(nq) => {
+                    let mut path = self.dir.join(nq);
+                    path.set_extension("png");
+                    fs_err::read(path)
+                }
+                None => Err(e),
+            })
+            .map_err(Into::into)
+    }
+
     fn transform(
         &self,
         options: &crate::Options,
         emoji: &EmojiImage,
-        sizes: &[u32],
-    ) -> Result<(), crate::Error> {
+        sizes: &[crate::Size],
+    ) -> Result<Vec<OutputFile>, crate::Error> {
         self.transform_image(options, emoji, sizes)
     }
 }
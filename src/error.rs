@@ -4,6 +4,14 @@ pub enum Error {
     NoEmoji,
     #[error("IO: {0}")]
     Io(#[from] std::io::Error),
+    #[error("Json: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Toml: {0}")]
+    Toml(#[from] toml::de::Error),
+    #[error("Template: {0}")]
+    Template(String),
+    #[error("Config: {0}")]
+    Config(String),
     #[error("Image: {0}")]
     Image(#[from] image::ImageError),
     #[error("Oxipng: {0}")]
@@ -16,4 +24,82 @@ pub enum Error {
     Zopfli(i32),
     #[error("Webp: {0:?}")]
     Webp(::webp::WebPEncodingError),
+    #[error("Avif: {0}")]
+    Avif(ravif::Error),
+    #[error("Jxl: {0}")]
+    Jxl(jpegxl_rs::EncodeError),
+    #[error("Font: {0}")]
+    Font(String),
+}
+
+/// The variant of an [`Error`] without any data.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    clap::ValueEnum,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorKind {
+    NoEmoji,
+    Io,
+    Json,
+    Toml,
+    Template,
+    Config,
+    Image,
+    Oxipng,
+    Resize,
+    Usvg,
+    Zopfli,
+    Webp,
+    Avif,
+    Jxl,
+    Font,
+}
+
+impl Error {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::NoEmoji => ErrorKind::NoEmoji,
+            Error::Io(_) => ErrorKind::Io,
+            Error::Json(_) => ErrorKind::Json,
+            Error::Toml(_) => ErrorKind::Toml,
+            Error::Template(_) => ErrorKind::Template,
+            Error::Config(_) => ErrorKind::Config,
+            Error::Image(_) => ErrorKind::Image,
+            Error::Oxipng(_) => ErrorKind::Oxipng,
+            Error::Resize(_) => ErrorKind::Resize,
+            Error::Usvg(_) => ErrorKind::Usvg,
+            Error::Zopfli(_) => ErrorKind::Zopfli,
+            Error::Webp(_) => ErrorKind::Webp,
+            Error::Avif(_) => ErrorKind::Avif,
+            Error::Jxl(_) => ErrorKind::Jxl,
+            Error::Font(_) => ErrorKind::Font,
+        }
+    }
+
+    /// Checks if this error means that the emoji isn't available from the provider
+    /// (as opposed to failing to process it).
+    pub fn is_missing(&self) -> bool {
+        match self {
+            Error::NoEmoji => true,
+            Error::Io(e) => e.kind() == std::io::ErrorKind::NotFound,
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = clap::ValueEnum::to_possible_value(self).unwrap();
+        f.write_str(value.get_name())
+    }
 }
@@ -0,0 +1,70 @@
+use crate::{EmojiImage, Error, Options, Provider, Size, Vendor, manifest::OutputFile};
+
+/// Tries the providers of other vendors if an emoji is missing from the primary one.
+///
+/// Only missing emojis (see [`Error::is_missing`]) fall through to the next provider, other
+/// errors are returned as is. Files supplied by a fallback record its vendor in
+/// [`OutputFile::vendor`].
+pub struct Fallback<'a> {
+    primary: Box<dyn Provider + 'a>,
+    fallbacks: Vec<(Vendor, Box<dyn Provider + 'a>)>,
+}
+
+impl<'a> Fallback<'a> {
+    pub fn new(primary: Box<dyn Provider + 'a>) -> Self {
+        Self {
+            primary,
+            fallbacks: Vec::new(),
+        }
+    }
+
+    /// Adds `provider` to the end of the chain.
+    pub fn or(mut self, vendor: Vendor, provider: Box<dyn Provider + 'a>) -> Self {
+        self.fallbacks.push((vendor, provider));
+        self
+    }
+
+    /// Calls `f` with each provider until one doesn't report a missing emoji.
+    ///
+    /// If the emoji is missing from all providers, the error of the primary one is returned.
+    fn first<T>(
+        &self,
+        mut f: impl FnMut(&dyn Provider) -> Result<T, Error>,
+    ) -> Result<(Option<&Vendor>, T), Error> {
+        let error = match f(self.primary.as_ref()) {
+            Err(e) if e.is_missing() => e,
+            result => return result.map(|it| (None, it)),
+        };
+        for (vendor, provider) in &self.fallbacks {
+            match f(provider.as_ref()) {
+                Err(e) if e.is_missing() => continue,
+                result => return result.map(|it| (Some(vendor), it)),
+            }
+        }
+        Err(error)
+    }
+}
+
+impl Provider for Fallback<'_> {
+    fn read_source(&self, emoji: &EmojiImage) -> Result<Vec<u8>, Error> {
+        let (vendor, mut source) = self.first(|it| it.read_source(emoji))?;
+        // switching to another vendor has to invalidate the cache
+        if let Some(vendor) = vendor {
+            source.extend_from_slice(vendor.to_string().as_bytes());
+        }
+        Ok(source)
+    }
+
+    fn transform(
+        &self,
+        options: &Options,
+        emoji: &EmojiImage,
+        sizes: &[Size],
+    ) -> Result<Vec<OutputFile>, Error> {
+        let (vendor, mut files) = self.first(|it| it.transform(options, emoji, sizes))?;
+        for file in &mut files {
+            file.vendor = vendor.cloned();
+        }
+        Ok(files)
+    }
+}
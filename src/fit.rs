@@ -0,0 +1,109 @@
+use fast_image_resize::{PixelType, images::Image};
+use image::RgbaImage;
+use tiny_skia::{Rect, Transform};
+
+use crate::{
+    Error, Size,
+    config::{FitMode, FitSettings, Padding, ResizeSettings},
+    resize::resize,
+};
+
+/// The dimensions of the output image for `size` and content of `width`x`height`.
+pub fn dimensions(size: Size, width: f32, height: f32) -> (u32, u32) {
+    size.dimensions(width / height)
+}
+
+/// The area of an output image (of `dimensions`) the content (of `width`x`height`) is placed in.
+pub fn placement(
+    settings: &FitSettings,
+    width: f32,
+    height: f32,
+    (out_width, out_height): (u32, u32),
+) -> Rect {
+    let (out_width, out_height) = (out_width as f32, out_height as f32);
+    let padding = match settings.padding {
+        Padding::Pixels(px) => px as f32,
+        Padding::Percent(percent) => out_width.min(out_height) * percent / 100.0,
+    };
+    let inner_width = (out_width - 2.0 * padding).max(1.0);
+    let inner_height = (out_height - 2.0 * padding).max(1.0);
+    let (width, height) = match settings.mode {
+        FitMode::Contain => {
+            let scale = (inner_width / width).min(inner_height / height);
+            (width * scale, height * scale)
+        }
+        FitMode::Fill => (inner_width, inner_height),
+    };
+    Rect::from_xywh(
+        (out_width - width) / 2.0,
+        (out_height - height) / 2.0,
+        width,
+        height,
+    )
+    .expect("the placement has a positive size")
+}
+
+/// The transform mapping `content` (e.g. the bounding box of an SVG) onto its placement.
+pub fn transform(settings: &FitSettings, content: Rect, dimensions: (u32, u32)) -> Transform {
+    let target = placement(settings, content.width(), content.height(), dimensions);
+    let sx = target.width() / content.width();
+    let sy = target.height() / content.height();
+    Transform::from_row(
+        sx,
+        0.0,
+        0.0,
+        sy,
+        target.x() - content.x() * sx,
+        target.y() - content.y() * sy,
+    )
+}
+
+/// Resizes `image` into its placement on a transparent image of `size`.
+pub fn fit_image(
+    settings: &FitSettings,
+    resize_settings: &ResizeSettings,
+    image: &RgbaImage,
+    size: Size,
+) -> Result<Image<'static>, Error> {
+    let (src_width, src_height) = (image.width() as f32, image.height() as f32);
+    let (out_width, out_height) = dimensions(size, src_width, src_height);
+    let rect = placement(settings, src_width, src_height, (out_width, out_height));
+    let x = (rect.x().round() as u32).min(out_width - 1);
+    let y = (rect.y().round() as u32).min(out_height - 1);
+    let width = (rect.width().round() as u32).clamp(1, out_width - x);
+    let height = (rect.height().round() as u32).clamp(1, out_height - y);
+    if (x, y, width, height) == (0, 0, out_width, out_height) {
+        return resize(resize_settings, image, width, height);
+    }
+
+    let resized = resize(resize_settings, image, width, height)?;
+    let mut dst_image = Image::new(out_width, out_height, PixelType::U8x4);
+    let row_len = width as usize * 4;
+    for (row, src) in resized.buffer().chunks_exact(row_len).enumerate() {
+        let offset = ((y as usize + row) * out_width as usize + x as usize) * 4;
+        dst_image.buffer_mut()[offset..offset + row_len].copy_from_slice(src);
+    }
+    Ok(dst_image)
+}
+
+/// Removes fully transparent rows and columns around the content.
+pub fn trim(image: RgbaImage) -> RgbaImage {
+    let mut bounds: Option<(u32, u32, u32, u32)> = None;
+    for (x, y, pixel) in image.enumerate_pixels() {
+        if pixel[3] == 0 {
+            continue;
+        }
+        bounds = Some(match bounds {
+            Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+            None => (x, y, x, y),
+        });
+    }
+    match bounds {
+        Some((x0, y0, x1, y1))
+            if (x0, y0, x1 + 1, y1 + 1) != (0, 0, image.width(), image.height()) =>
+        {
+            image::imageops::crop_imm(&image, x0, y0, x1 - x0 + 1, y1 - y0 + 1).to_image()
+        }
+        _ => image,
+    }
+}
@@ -0,0 +1,957 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    Error,
+    config::{FontFormat, FontSettings},
+    manifest::{Manifest, OutputFile},
+};
+
+/// Units per em of the generated fonts.
+const UPEM: u16 = 2048;
+/// Top of an emoji relative to the baseline (0.88 em).
+const EMOJI_TOP: i16 = 1802;
+/// Bottom of an emoji relative to the baseline (-0.12 em).
+const EMOJI_BOTTOM: i16 = EMOJI_TOP - UPEM as i16;
+const ASCENDER: i16 = 1900;
+const DESCENDER: i16 = -500;
+
+/// A glyph of the generated font.
+struct Glyph {
+    /// PNGs by strike size (the height of the output size)
+    bitmaps: BTreeMap<u32, OutputFile>,
+    advance: u16,
+}
+
+/// The glyphs, character map, and ligatures of a font.
+struct Layout {
+    glyphs: Vec<Glyph>,
+    cmap: BTreeMap<u32, u16>,
+    /// Ligatures by their components
+    ligatures: BTreeMap<Vec<u16>, u16>,
+    strikes: Vec<u32>,
+}
+
+/// Builds a color bitmap font from the PNGs in `manifest` and writes it to
+/// `{root}/{vendor}/{vendor}-{format}.ttf`.
+///
+/// Every size with PNGs becomes a strike (unless [`FontSettings::sizes`] is set). Single
+/// codepoints are mapped through `cmap`, sequences (ZWJ sequences, flags, skin tones, keycaps) are
+/// ligatures in the `ccmp` feature of `GSUB`.
+pub fn write(
+    root: &Path,
+    vendor: &str,
+    manifest: &Manifest,
+    settings: &FontSettings,
+) -> Result<PathBuf, Error> {
+    let family = settings
+        .family
+        .clone()
+        .unwrap_or_else(|| format!("emojicrunch {vendor}"));
+    let data = build(root, manifest, settings, &family)?;
+    let path = root
+        .join(vendor)
+        .join(format!("{vendor}-{}.ttf", settings.format.as_str()));
+    fs_err::create_dir_all(root.join(vendor))?;
+    fs_err::write(&path, data)?;
+    Ok(path)
+}
+
+/// Builds a color bitmap font from the PNGs in `manifest` (relative to `root`).
+pub fn build(
+    root: &Path,
+    manifest: &Manifest,
+    settings: &FontSettings,
+    family: &str,
+) -> Result<Vec<u8>, Error> {
+    let layout = Layout::new(manifest, settings)?;
+    if layout.strikes.is_empty() {
+        return Err(Error::Font("no PNGs to build the font from".to_owned()));
+    }
+
+    let mut tables: Vec<([u8; 4], Vec<u8>)> = vec![
+        (*b"OS/2", layout.os2()),
+        (*b"cmap", layout.cmap()),
+        (*b"glyf", Vec::new()),
+        (*b"head", layout.head()),
+        (*b"hhea", layout.hhea()),
+        (*b"hmtx", layout.hmtx()),
+        (*b"loca", vec![0; (layout.glyphs.len() + 1) * 2]),
+        (*b"maxp", layout.maxp()),
+        (*b"name", name(family)),
+        (*b"post", post()),
+    ];
+    if !layout.ligatures.is_empty() {
+        tables.push((*b"GSUB", layout.gsub()));
+    }
+    match settings.format {
+        FontFormat::Cbdt => {
+            let (cbdt, cblc) = layout.cbdt_cblc(root)?;
+            tables.push((*b"CBDT", cbdt));
+            tables.push((*b"CBLC", cblc));
+        }
+        FontFormat::Sbix => tables.push((*b"sbix", layout.sbix(root)?)),
+    }
+    Ok(assemble(tables))
+}
+
+impl Layout {
+    fn new(manifest: &Manifest, settings: &FontSettings) -> Result<Self, Error> {
+        let mut glyphs = vec![Glyph {
+            bitmaps: BTreeMap::new(),
+            advance: UPEM,
+        }];
+        let mut strikes = BTreeSet::new();
+        let mut sequences = Vec::new();
+
+        for entry in &manifest.emojis {
+            let mut bitmaps: BTreeMap<u32, OutputFile> = BTreeMap::new();
+            for file in entry
+                .files
+                .iter()
+                .filter(|it| it.format == "png")
+                .filter(|it| settings.sizes.is_empty() || settings.sizes.contains(&it.size.height))
+            {
+                // a font has a single strike per ppem
+                if let Some(other) = bitmaps.insert(file.size.height, file.clone()) {
+                    if other.size != file.size {
+                        return Err(Error::Font(format!(
+                            "{} and {} have the same height, only one of them can be a strike \
+                             (select the strikes with `font.sizes`)",
+                            other.size, file.size
+                        )));
+                    }
+                }
+            }
+            let Some(first) = bitmaps.values().next() else {
+                continue;
+            };
+            if let Some(large) = bitmaps.values().find(|it| {
+                settings.format == FontFormat::Cbdt && (it.width > 127 || it.height > 127)
+            }) {
+                return Err(Error::Font(format!(
+                    "CBDT bitmaps can't be larger than 127px (got {}x{}px)",
+                    large.width, large.height
+                )));
+            }
+            strikes.extend(bitmaps.keys().copied());
+
+            let advance = (first.width as u64 * UPEM as u64 / first.height as u64) as u16;
+            let glyph = glyphs.len() as u16;
+            glyphs.push(Glyph { bitmaps, advance });
+
+            sequences.push((parse_sequence(&entry.emoji.unified)?, glyph));
+            if let Some(ref non_qualified) = entry.emoji.non_qualified {
+                sequences.push((parse_sequence(non_qualified)?, glyph));
+            }
+        }
+
+        // single codepoints are mapped to their emoji directly
+        let mut cmap = BTreeMap::new();
+        for (sequence, glyph) in &sequences {
+            if let [codepoint] = sequence[..] {
+                cmap.entry(codepoint).or_insert(*glyph);
+            }
+        }
+
+        // all other codepoints of sequences get an empty glyph
+        let mut ligatures = BTreeMap::new();
+        for (sequence, glyph) in &sequences {
+            if sequence.len() < 2 {
+                continue;
+            }
+            let mut components = Vec::with_capacity(sequence.len());
+            for &codepoint in sequence {
+                let component = *cmap.entry(codepoint).or_insert_with(|| {
+                    glyphs.push(Glyph {
+                        bitmaps: BTreeMap::new(),
+                        advance: if is_default_ignorable(codepoint) {
+                            0
+                        } else {
+                            UPEM
+                        },
+                    });
+                    (glyphs.len() - 1) as u16
+                });
+                components.push(component);
+            }
+            ligatures.entry(components).or_insert(*glyph);
+        }
+
+        if glyphs.len() > u16::MAX as usize {
+            return Err(Error::Font("too many glyphs".to_owned()));
+        }
+
+        Ok(Self {
+            glyphs,
+            cmap,
+            ligatures,
+            strikes: strikes.into_iter().collect(),
+        })
+    }
+
+    fn num_glyphs(&self) -> u16 {
+        self.glyphs.len() as u16
+    }
+
+    fn max_advance(&self) -> u16 {
+        self.glyphs
+            .iter()
+            .map(|it| it.advance)
+            .max()
+            .unwrap_or(UPEM)
+    }
+
+    fn max_context(&self) -> u16 {
+        self.ligatures.keys().map(Vec::len).max().unwrap_or(1) as u16
+    }
+
+    fn head(&self) -> Vec<u8> {
+        let mut w = Writer::default();
+        w.u16(1); // majorVersion
+        w.u16(0); // minorVersion
+        w.u32(0x0001_0000); // fontRevision
+        w.u32(0); // checksumAdjustment (set in `assemble`)
+        w.u32(0x5F0F_3CF5); // magicNumber
+        w.u16(0b1011); // flags: baseline at y=0, lsb at x=0, integer ppem
+        w.u16(UPEM);
+        w.u64(0); // created
+        w.u64(0); // modified
+        w.i16(0); // xMin
+        w.i16(EMOJI_BOTTOM); // yMin
+        w.i16(self.max_advance() as i16); // xMax
+        w.i16(EMOJI_TOP); // yMax
+        w.u16(0); // macStyle
+        w.u16(8); // lowestRecPPEM
+        w.i16(2); // fontDirectionHint
+        w.i16(0); // indexToLocFormat (short)
+        w.i16(0); // glyphDataFormat
+        w.0
+    }
+
+    fn hhea(&self) -> Vec<u8> {
+        let max_advance = self.max_advance();
+        let mut w = Writer::default();
+        w.u16(1); // majorVersion
+        w.u16(0); // minorVersion
+        w.i16(ASCENDER);
+        w.i16(DESCENDER);
+        w.i16(0); // lineGap
+        w.u16(max_advance); // advanceWidthMax
+        w.i16(0); // minLeftSideBearing
+        w.i16(0); // minRightSideBearing
+        w.i16(max_advance as i16); // xMaxExtent
+        w.i16(1); // caretSlopeRise
+        w.i16(0); // caretSlopeRun
+        w.i16(0); // caretOffset
+        for _ in 0..4 {
+            w.i16(0); // reserved
+        }
+        w.i16(0); // metricDataFormat
+        w.u16(self.num_glyphs()); // numberOfHMetrics
+        w.0
+    }
+
+    fn hmtx(&self) -> Vec<u8> {
+        let mut w = Writer::default();
+        for glyph in &self.glyphs {
+            w.u16(glyph.advance);
+            w.i16(0); // lsb
+        }
+        w.0
+    }
+
+    fn maxp(&self) -> Vec<u8> {
+        let mut w = Writer::default();
+        w.u32(0x0001_0000);
+        w.u16(self.num_glyphs());
+        w.u16(0); // maxPoints
+        w.u16(0); // maxContours
+        w.u16(0); // maxCompositePoints
+        w.u16(0); // maxCompositeContours
+        w.u16(2); // maxZones
+        for _ in 0..8 {
+            // maxTwilightPoints ... maxComponentDepth
+            w.u16(0);
+        }
+        w.0
+    }
+
+    fn os2(&self) -> Vec<u8> {
+        let first = self.cmap.keys().next().copied().unwrap_or(0).min(0xFFFF) as u16;
+        let last = self.cmap.keys().last().copied().unwrap_or(0).min(0xFFFF) as u16;
+
+        let mut w = Writer::default();
+        w.u16(4); // version
+        w.i16(UPEM as i16); // xAvgCharWidth
+        w.u16(400); // usWeightClass
+        w.u16(5); // usWidthClass
+        w.u16(0); // fsType (installable)
+        for value in [1331, 1433, 0, 286, 1331, 1433, 0, 983] {
+            // ySubscript* and ySuperscript*
+            w.i16(value);
+        }
+        w.i16(102); // yStrikeoutSize
+        w.i16(530); // yStrikeoutPosition
+        w.i16(0); // sFamilyClass
+        w.bytes(&[0; 10]); // panose
+        w.u32(0); // ulUnicodeRange1
+        w.u32(1 << (57 - 32)); // ulUnicodeRange2 (non-plane 0)
+        w.u32(0); // ulUnicodeRange3
+        w.u32(0); // ulUnicodeRange4
+        w.bytes(b"NONE"); // achVendID
+        w.u16(0x40); // fsSelection (regular)
+        w.u16(first); // usFirstCharIndex
+        w.u16(last); // usLastCharIndex
+        w.i16(ASCENDER); // sTypoAscender
+        w.i16(DESCENDER); // sTypoDescender
+        w.i16(0); // sTypoLineGap
+        w.u16(ASCENDER as u16); // usWinAscent
+        w.u16(-DESCENDER as u16); // usWinDescent
+        w.u32(1); // ulCodePageRange1 (latin 1)
+        w.u32(0); // ulCodePageRange2
+        w.i16(0); // sxHeight
+        w.i16(0); // sCapHeight
+        w.u16(0); // usDefaultChar
+        w.u16(0x20); // usBreakChar
+        w.u16(self.max_context()); // usMaxContext
+        w.0
+    }
+
+    fn cmap(&self) -> Vec<u8> {
+        let format4 = self.cmap_format4();
+        let format12 = self.cmap_format12();
+
+        let mut w = Writer::default();
+        w.u16(0); // version
+        w.u16(3); // numTables
+        let header = 4 + 3 * 8;
+        let format4_offset = header as u32;
+        let format12_offset = format4_offset + format4.len() as u32;
+        // (platform, encoding, offset) sorted by platform and encoding
+        for (platform, encoding, offset) in [
+            (0, 4, format12_offset),
+            (3, 1, format4_offset),
+            (3, 10, format12_offset),
+        ] {
+            w.u16(platform);
+            w.u16(encoding);
+            w.u32(offset);
+        }
+        w.bytes(&format4);
+        w.bytes(&format12);
+        w.0
+    }
+
+    /// Segment mapping for the BMP, one segment per run of codepoints and glyphs.
+    fn cmap_format4(&self) -> Vec<u8> {
+        let mut segments = runs(self.cmap.range(..0xFFFF));
+        segments.push((0xFFFF, 0xFFFF, 0));
+        let seg_count = segments.len() as u16;
+        let (search_range, entry_selector, range_shift) = search_params(seg_count, 2);
+
+        let mut w = Writer::default();
+        w.u16(4); // format
+        w.u16(16 + 8 * seg_count); // length
+        w.u16(0); // language
+        w.u16(seg_count * 2);
+        w.u16(search_range);
+        w.u16(entry_selector);
+        w.u16(range_shift);
+        for &(_, end, _) in &segments {
+            w.u16(end as u16);
+        }
+        w.u16(0); // reservedPad
+        for &(start, _, _) in &segments {
+            w.u16(start as u16);
+        }
+        for &(start, end, glyph) in &segments {
+            let delta = if end == 0xFFFF {
+                1
+            } else {
+                glyph.wrapping_sub(start as u16)
+            };
+            w.u16(delta);
+        }
+        for _ in &segments {
+            w.u16(0); // idRangeOffset
+        }
+        w.0
+    }
+
+    /// Segmented coverage for all codepoints.
+    fn cmap_format12(&self) -> Vec<u8> {
+        let groups = runs(self.cmap.iter());
+        let mut w = Writer::default();
+        w.u16(12); // format
+        w.u16(0); // reserved
+        w.u32(16 + 12 * groups.len() as u32); // length
+        w.u32(0); // language
+        w.u32(groups.len() as u32);
+        for (start, end, glyph) in groups {
+            w.u32(start);
+            w.u32(end);
+            w.u32(glyph as u32);
+        }
+        w.0
+    }
+
+    /// A single `ccmp` lookup with all ligatures, wrapped in extension subtables, because the
+    /// ligatures of a large emoji set don't fit into 16-bit offsets.
+    fn gsub(&self) -> Vec<u8> {
+        // ligature sets by their first glyph
+        let mut sets: BTreeMap<u16, Vec<(&[u16], u16)>> = BTreeMap::new();
+        for (components, &glyph) in &self.ligatures {
+            sets.entry(components[0])
+                .or_default()
+                .push((&components[1..], glyph));
+        }
+        for set in sets.values_mut() {
+            // longer sequences must be tried first
+            set.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then(a.0.cmp(b.0)));
+        }
+
+        let mut subtables = Vec::new();
+        let mut chunk = BTreeMap::new();
+        let mut chunk_size = 0;
+        for (first, set) in sets {
+            let set_size = 6 + set.iter().map(|(it, _)| 6 + 2 * it.len()).sum::<usize>();
+            if chunk_size + set_size > 0xF000 && !chunk.is_empty() {
+                subtables.push(ligature_subst(&std::mem::take(&mut chunk)));
+                chunk_size = 0;
+            }
+            chunk_size += set_size;
+            chunk.insert(first, set);
+        }
+        if !chunk.is_empty() {
+            subtables.push(ligature_subst(&chunk));
+        }
+
+        let mut w = Writer::default();
+        w.u16(1); // majorVersion
+        w.u16(0); // minorVersion
+        w.u16(10); // scriptListOffset
+        w.u16(30); // featureListOffset
+        w.u16(44); // lookupListOffset
+
+        // ScriptList (10): DFLT with a default LangSys
+        w.u16(1); // scriptCount
+        w.bytes(b"DFLT");
+        w.u16(8); // scriptOffset
+        w.u16(4); // defaultLangSysOffset
+        w.u16(0); // langSysCount
+        w.u16(0); // lookupOrderOffset
+        w.u16(0xFFFF); // requiredFeatureIndex
+        w.u16(1); // featureIndexCount
+        w.u16(0); // featureIndices
+
+        // FeatureList (30): ccmp
+        w.u16(1); // featureCount
+        w.bytes(b"ccmp");
+        w.u16(8); // featureOffset
+        w.u16(0); // featureParamsOffset
+        w.u16(1); // lookupIndexCount
+        w.u16(0); // lookupListIndices
+
+        // LookupList (44)
+        w.u16(1); // lookupCount
+        w.u16(4); // lookupOffsets
+
+        // Lookup (48): extension subtables
+        let lookup_start = w.0.len();
+        let count = subtables.len();
+        w.u16(7); // lookupType (extension)
+        w.u16(0); // lookupFlag
+        w.u16(count as u16);
+        let extensions_offset = 6 + 2 * count;
+        for i in 0..count {
+            w.u16((extensions_offset + 8 * i) as u16);
+        }
+        let mut subtable_offset = extensions_offset + 8 * count;
+        for (i, subtable) in subtables.iter().enumerate() {
+            w.u16(1); // substFormat
+            w.u16(4); // extensionLookupType (ligature)
+            w.u32((subtable_offset - (extensions_offset + 8 * i)) as u32);
+            subtable_offset += subtable.len();
+        }
+        debug_assert_eq!(w.0.len() - lookup_start, extensions_offset + 8 * count);
+        for subtable in subtables {
+            w.bytes(&subtable);
+        }
+        w.0
+    }
+
+    /// Google's color bitmap tables with PNG glyphs (format 17) and one strike per size.
+    fn cbdt_cblc(&self, root: &Path) -> Result<(Vec<u8>, Vec<u8>), Error> {
+        let mut cbdt = Writer::default();
+        cbdt.u16(3); // majorVersion
+        cbdt.u16(0); // minorVersion
+
+        // a single index subtable (format 1) per strike, covering all glyphs
+        let first_glyph = 1;
+        let last_glyph = self.num_glyphs() - 1;
+        let mut index_subtables = Vec::with_capacity(self.strikes.len());
+        let mut line_metrics = Vec::with_capacity(self.strikes.len());
+        for &size in &self.strikes {
+            let image_data_offset = cbdt.0.len() as u32;
+            let mut index = Writer::default();
+            index.u16(1); // indexFormat
+            index.u16(17); // imageFormat (small metrics, PNG)
+            index.u32(image_data_offset);
+
+            let mut max_width = 0;
+            for glyph in &self.glyphs[first_glyph as usize..] {
+                index.u32(cbdt.0.len() as u32 - image_data_offset);
+                let Some(file) = glyph.bitmaps.get(&size) else {
+                    continue;
+                };
+                let png = fs_err::read(root.join(&file.path))?;
+                let (bearing_y, _) = vertical_placement(file);
+                max_width = max_width.max(file.width);
+                // smallGlyphMetrics
+                cbdt.u8(file.height as u8);
+                cbdt.u8(file.width as u8);
+                cbdt.i8(0); // bearingX
+                cbdt.i8(bearing_y as i8);
+                cbdt.u8(file.width as u8); // advance
+                cbdt.u32(png.len() as u32);
+                cbdt.bytes(&png);
+            }
+            index.u32(cbdt.0.len() as u32 - image_data_offset);
+            index_subtables.push(index.0);
+
+            let ascender = (ASCENDER as f32 * size as f32 / UPEM as f32).round() as i8;
+            let descender = (DESCENDER as f32 * size as f32 / UPEM as f32).round() as i8;
+            line_metrics.push((ascender, descender, max_width.min(255) as u8));
+        }
+
+        let mut cblc = Writer::default();
+        cblc.u16(3); // majorVersion
+        cblc.u16(0); // minorVersion
+        cblc.u32(self.strikes.len() as u32);
+        let mut array_offset = 8 + 48 * self.strikes.len();
+        for (i, &size) in self.strikes.iter().enumerate() {
+            let tables_size = 8 + index_subtables[i].len();
+            cblc.u32(array_offset as u32); // indexSubTableArrayOffset
+            cblc.u32(tables_size as u32); // indexTablesSize
+            cblc.u32(1); // numberOfIndexSubTables
+            cblc.u32(0); // colorRef
+            let (ascender, descender, width_max) = line_metrics[i];
+            for _ in 0..2 {
+                // hori and vert SbitLineMetrics
+                cblc.i8(ascender);
+                cblc.i8(descender);
+                cblc.u8(width_max);
+                cblc.i8(1); // caretSlopeNumerator
+                cblc.i8(0); // caretSlopeDenominator
+                cblc.i8(0); // caretOffset
+                cblc.i8(0); // minOriginSB
+                cblc.i8(0); // minAdvanceSB
+                cblc.i8(ascender); // maxBeforeBL
+                cblc.i8(descender); // minAfterBL
+                cblc.i8(0); // pad1
+                cblc.i8(0); // pad2
+            }
+            cblc.u16(first_glyph); // startGlyphIndex
+            cblc.u16(last_glyph); // endGlyphIndex
+            cblc.u8(size.min(255) as u8); // ppemX
+            cblc.u8(size.min(255) as u8); // ppemY
+            cblc.u8(32); // bitDepth
+            cblc.i8(1); // flags (horizontal metrics)
+            array_offset += tables_size;
+        }
+        for index in index_subtables {
+            // IndexSubTableArray with a single record, followed by the subtable
+            cblc.u16(first_glyph);
+            cblc.u16(last_glyph);
+            cblc.u32(8); // additionalOffsetToIndexSubtable
+            cblc.bytes(&index);
+        }
+        Ok((cbdt.0, cblc.0))
+    }
+
+    /// Apple's standard bitmap graphics table with PNG glyphs and one strike per size.
+    fn sbix(&self, root: &Path) -> Result<Vec<u8>, Error> {
+        let mut w = Writer::default();
+        w.u16(1); // version
+        w.u16(1); // flags (bit 0 must be set)
+        w.u32(self.strikes.len() as u32);
+        let offsets_start = w.0.len();
+        for _ in &self.strikes {
+            w.u32(0); // strikeOffsets (patched below)
+        }
+
+        for (i, &size) in self.strikes.iter().enumerate() {
+            let strike_start = w.0.len();
+            w.set_u32(offsets_start + 4 * i, strike_start as u32);
+            w.u16(size.min(u16::MAX as u32) as u16); // ppem
+            w.u16(72); // ppi
+            let glyph_offsets_start = w.0.len();
+            for _ in 0..=self.glyphs.len() {
+                w.u32(0); // glyphDataOffsets (patched below)
+            }
+            for (glyph_index, glyph) in self.glyphs.iter().enumerate() {
+                w.set_u32(
+                    glyph_offsets_start + 4 * glyph_index,
+                    (w.0.len() - strike_start) as u32,
+                );
+                let Some(file) = glyph.bitmaps.get(&size) else {
+                    continue;
+                };
+                let png = fs_err::read(root.join(&file.path))?;
+                let (_, origin_y) = vertical_placement(file);
+                w.i16(0); // originOffsetX
+                w.i16(origin_y as i16); // originOffsetY
+                w.bytes(b"png ");
+                w.bytes(&png);
+            }
+            w.set_u32(
+                glyph_offsets_start + 4 * self.glyphs.len(),
+                (w.0.len() - strike_start) as u32,
+            );
+            w.pad();
+        }
+        Ok(w.0)
+    }
+}
+
+/// Returns the top of the bitmap (`bearingY`) and its bottom relative to the baseline in pixels.
+fn vertical_placement(file: &OutputFile) -> (i32, i32) {
+    let top = (EMOJI_TOP as f32 * file.height as f32 / UPEM as f32).round() as i32;
+    (top, top - file.height as i32)
+}
+
+/// `LigatureSubstFormat1` for the ligature sets of some first glyphs.
+fn ligature_subst(sets: &BTreeMap<u16, Vec<(&[u16], u16)>>) -> Vec<u8> {
+    let count = sets.len();
+    let coverage_offset = 6 + 2 * count;
+    let mut w = Writer::default();
+    w.u16(1); // substFormat
+    w.u16(coverage_offset as u16);
+    w.u16(count as u16); // ligatureSetCount
+    let offsets_start = w.0.len();
+    for _ in 0..count {
+        w.u16(0); // ligatureSetOffsets (patched below)
+    }
+
+    // Coverage format 1
+    w.u16(1);
+    w.u16(count as u16);
+    for &first in sets.keys() {
+        w.u16(first);
+    }
+
+    for (i, set) in sets.values().enumerate() {
+        let set_start = w.0.len();
+        w.set_u16(offsets_start + 2 * i, set_start as u16);
+        w.u16(set.len() as u16); // ligatureCount
+        let mut ligature_offset = 2 + 2 * set.len();
+        for (rest, _) in set {
+            w.u16(ligature_offset as u16);
+            ligature_offset += 4 + 2 * rest.len();
+        }
+        for (rest, glyph) in set {
+            w.u16(*glyph); // ligatureGlyph
+            w.u16(rest.len() as u16 + 1); // componentCount
+            for &component in *rest {
+                w.u16(component);
+            }
+        }
+    }
+    w.0
+}
+
+fn name(family: &str) -> Vec<u8> {
+    let postscript: String = family
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+        .collect();
+    let records = [
+        (1, family.to_owned()),
+        (2, "Regular".to_owned()),
+        (3, format!("{family};{}", env!("CARGO_PKG_VERSION"))),
+        (4, family.to_owned()),
+        (5, format!("Version {}", env!("CARGO_PKG_VERSION"))),
+        (6, format!("{postscript}-Regular")),
+    ];
+
+    let mut strings = Vec::new();
+    let mut w = Writer::default();
+    w.u16(0); // version
+    w.u16(records.len() as u16);
+    w.u16(6 + 12 * records.len() as u16); // storageOffset
+    for (id, value) in records {
+        let encoded: Vec<u8> = value.encode_utf16().flat_map(u16::to_be_bytes).collect();
+        w.u16(3); // platformID (Windows)
+        w.u16(1); // encodingID (Unicode BMP)
+        w.u16(0x409); // languageID (en-US)
+        w.u16(id);
+        w.u16(encoded.len() as u16);
+        w.u16(strings.len() as u16);
+        strings.extend(encoded);
+    }
+    w.bytes(&strings);
+    w.0
+}
+
+fn post() -> Vec<u8> {
+    let mut w = Writer::default();
+    w.u32(0x0003_0000); // version (no glyph names)
+    w.u32(0); // italicAngle
+    w.i16(-100); // underlinePosition
+    w.i16(50); // underlineThickness
+    w.u32(0); // isFixedPitch
+    for _ in 0..4 {
+        w.u32(0); // min/maxMemType42, min/maxMemType1
+    }
+    w.0
+}
+
+/// Writes the table directory and all tables, and computes the checksums.
+fn assemble(mut tables: Vec<([u8; 4], Vec<u8>)>) -> Vec<u8> {
+    tables.sort_by(|a, b| a.0.cmp(&b.0));
+    let (search_range, entry_selector, range_shift) = search_params(tables.len() as u16, 16);
+
+    let mut w = Writer::default();
+    w.u32(0x0001_0000); // sfntVersion
+    w.u16(tables.len() as u16);
+    w.u16(search_range);
+    w.u16(entry_selector);
+    w.u16(range_shift);
+
+    let mut offset = 12 + 16 * tables.len();
+    let mut head_offset = None;
+    for (tag, data) in &tables {
+        if tag == b"head" {
+            head_offset = Some(offset);
+        }
+        w.bytes(tag);
+        w.u32(checksum(data));
+        w.u32(offset as u32);
+        w.u32(data.len() as u32);
+        offset += data.len().next_multiple_of(4);
+    }
+    for (_, data) in &tables {
+        w.bytes(data);
+        w.pad();
+    }
+
+    if let Some(head_offset) = head_offset {
+        let adjustment = 0xB1B0_AFBAu32.wrapping_sub(checksum(&w.0));
+        w.set_u32(head_offset + 8, adjustment);
+    }
+    w.0
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+/// `searchRange`, `entrySelector`, and `rangeShift` for binary searches over `count` items.
+fn search_params(count: u16, item_size: u16) -> (u16, u16, u16) {
+    let entry_selector = if count == 0 { 0 } else { count.ilog2() as u16 };
+    let search_range = (1 << entry_selector) * item_size;
+    (
+        search_range,
+        entry_selector,
+        (count * item_size).saturating_sub(search_range),
+    )
+}
+
+/// Groups codepoints into runs of consecutive codepoints with consecutive glyphs.
+fn runs<'a>(it: impl Iterator<Item = (&'a u32, &'a u16)>) -> Vec<(u32, u32, u16)> {
+    let mut runs: Vec<(u32, u32, u16)> = Vec::new();
+    for (&codepoint, &glyph) in it {
+        match runs.last_mut() {
+            Some((start, end, first))
+                if *end + 1 == codepoint
+                    && *first as u32 + (codepoint - *start) == glyph as u32 =>
+            {
+                *end = codepoint
+            }
+            _ => runs.push((codepoint, codepoint, glyph)),
+        }
+    }
+    runs
+}
+
+/// Parses codepoints like `1f44d-1f3fb`.
+fn parse_sequence(unified: &str) -> Result<Vec<u32>, Error> {
+    unified
+        .split('-')
+        .map(|it| {
+            u32::from_str_radix(it, 16)
+                .map_err(|_| Error::Font(format!("invalid codepoints '{unified}'")))
+        })
+        .collect()
+}
+
+/// Joiners, variation selectors, tags, and the keycap don't take up any space on their own.
+fn is_default_ignorable(codepoint: u32) -> bool {
+    matches!(codepoint, 0x200D | 0xFE00..=0xFE0F | 0x20E3 | 0xE0020..=0xE007F)
+}
+
+/// Big-endian writer for font tables.
+#[derive(Default)]
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    fn i8(&mut self, value: i8) {
+        self.0.push(value as u8);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.0.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn i16(&mut self, value: i16) {
+        self.0.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.0.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn bytes(&mut self, data: &[u8]) {
+        self.0.extend_from_slice(data);
+    }
+
+    fn set_u16(&mut self, at: usize, value: u16) {
+        self.0[at..at + 2].copy_from_slice(&value.to_be_bytes());
+    }
+
+    fn set_u32(&mut self, at: usize, value: u32) {
+        self.0[at..at + 4].copy_from_slice(&value.to_be_bytes());
+    }
+
+    /// Pads to a multiple of four bytes.
+    fn pad(&mut self) {
+        self.0.resize(self.0.len().next_multiple_of(4), 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use image::ImageEncoder;
+
+    use super::*;
+    use crate::{
+        Size,
+        manifest::{EmojiInfo, Entry},
+    };
+
+    /// A manifest with solid PNGs for 👍 and 👍🏻 written to a temporary directory.
+    fn manifest(root: &Path, sizes: &[Size]) -> Manifest {
+        let mut manifest = Manifest::default();
+        for unified in ["1f44d", "1f44d-1f3fb"] {
+            let files = sizes
+                .iter()
+                .map(|&size| {
+                    let (width, height) = size.dimensions(1.0);
+                    let mut png = Vec::new();
+                    image::codecs::png::PngEncoder::new(&mut png)
+                        .write_image(
+                            &vec![255; (width * height * 4) as usize],
+                            width,
+                            height,
+                            image::ExtendedColorType::Rgba8,
+                        )
+                        .unwrap();
+                    let path = PathBuf::from(format!("test/{size}/{unified}.png"));
+                    OutputFile::write(root, path, size, (width, height), &png).unwrap()
+                })
+                .collect();
+            manifest.emojis.push(Entry {
+                emoji: EmojiInfo {
+                    unified: unified.to_owned(),
+                    non_qualified: None,
+                    short_name: "+1".to_owned(),
+                    skin_tone: None,
+                },
+                files,
+            });
+        }
+        manifest
+    }
+
+    fn build_font(format: FontFormat, sizes: &[Size]) -> Result<Vec<u8>, Error> {
+        // tests run in parallel, so each build gets its own directory
+        static BUILDS: AtomicUsize = AtomicUsize::new(0);
+        let root = std::env::temp_dir().join(format!(
+            "emojicrunch-fontbuild-{}-{}",
+            std::process::id(),
+            BUILDS.fetch_add(1, Ordering::Relaxed)
+        ));
+        let settings = FontSettings {
+            format,
+            family: None,
+            sizes: Vec::new(),
+        };
+        let result = build(&root, &manifest(&root, sizes), &settings, "test");
+        fs_err::remove_dir_all(&root).unwrap();
+        result
+    }
+
+    fn round_trip(format: FontFormat) {
+        let data = build_font(format, &[Size::square(22), Size::square(44)]).unwrap();
+        let face = ttf_parser::Face::parse(&data, 0).unwrap();
+
+        let glyph = face.glyph_index('\u{1f44d}').unwrap();
+        for size in [22, 44] {
+            let image = face.glyph_raster_image(glyph, size).unwrap();
+            assert_eq!(image.format, ttf_parser::RasterImageFormat::PNG);
+            assert_eq!(image.pixels_per_em, size);
+            assert_eq!((image.width, image.height), (size, size));
+        }
+
+        // the skin tone modifier is a ligature
+        let face = rustybuzz::Face::from_slice(&data, 0).unwrap();
+        let mut buffer = rustybuzz::UnicodeBuffer::new();
+        buffer.push_str("\u{1f44d}\u{1f3fb}");
+        let shaped = rustybuzz::shape(&face, &[], buffer);
+        assert_eq!(shaped.len(), 1);
+        let ligature = ttf_parser::GlyphId(shaped.glyph_infos()[0].glyph_id as u16);
+        assert_ne!(ligature, glyph);
+        assert!(face.glyph_raster_image(ligature, 22).is_some());
+    }
+
+    #[test]
+    fn cbdt_round_trip() {
+        round_trip(FontFormat::Cbdt);
+    }
+
+    #[test]
+    fn sbix_round_trip() {
+        round_trip(FontFormat::Sbix);
+    }
+
+    #[test]
+    fn reject_invalid_strikes() {
+        let same_height = [Size::square(22), "28x22".parse().unwrap()];
+        assert!(matches!(
+            build_font(FontFormat::Sbix, &same_height),
+            Err(Error::Font(_))
+        ));
+        let too_wide = ["200x100".parse().unwrap()];
+        assert!(matches!(
+            build_font(FontFormat::Cbdt, &too_wide),
+            Err(Error::Font(_))
+        ));
+    }
+}
@@ -0,0 +1,69 @@
+use jpegxl_rs::{
+    encode::{EncoderFrame, EncoderResult, EncoderSpeed},
+    encoder_builder,
+};
+
+use crate::{
+    config::JxlSettings,
+    optimize::{OptimizableImage, Optimizer},
+};
+
+pub struct Jxl;
+
+impl Optimizer for Jxl {
+    fn name(&self) -> &'static str {
+        "jxl"
+    }
+
+    fn optimize_fir(
+        &self,
+        options: &crate::Options,
+        image: fast_image_resize::images::Image,
+    ) -> Result<Vec<u8>, crate::Error> {
+        optimize(&options.jxl, image)
+    }
+
+    fn optimize_skia(
+        &self,
+        options: &crate::Options,
+        image: tiny_skia::Pixmap,
+    ) -> Result<Vec<u8>, crate::Error> {
+        optimize(&options.jxl, image)
+    }
+}
+
+fn speed(effort: u8) -> EncoderSpeed {
+    match effort {
+        0 | 1 => EncoderSpeed::Lightning,
+        2 => EncoderSpeed::Thunder,
+        3 => EncoderSpeed::Falcon,
+        4 => EncoderSpeed::Cheetah,
+        5 => EncoderSpeed::Hare,
+        6 => EncoderSpeed::Wombat,
+        7 => EncoderSpeed::Squirrel,
+        8 => EncoderSpeed::Kitten,
+        _ => EncoderSpeed::Tortoise,
+    }
+}
+
+fn optimize(settings: &JxlSettings, image: impl OptimizableImage) -> Result<Vec<u8>, crate::Error> {
+    let width = image.width();
+    let height = image.height();
+    // the encoder isn't thread-safe, so it has to be created for every image
+    let mut encoder = encoder_builder()
+        .has_alpha(true)
+        .lossless(settings.lossless)
+        // lossless (modular) mode requires the original color profile
+        .uses_original_profile(settings.lossless)
+        .quality(settings.distance.clamp(0.0, 15.0))
+        .speed(speed(settings.effort))
+        .build()
+        .map_err(crate::Error::Jxl)?;
+
+    let data = image.into_data();
+    let frame = EncoderFrame::new(&data).num_channels(4);
+    let result: EncoderResult<u8> = encoder
+        .encode_frame(&frame, width, height)
+        .map_err(crate::Error::Jxl)?;
+    Ok(result.data)
+}
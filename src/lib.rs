@@ -0,0 +1,172 @@
+//! Render, resize and crunch emojis from various vendors.
+//!
+//! The emoji catalog is read from an [emoji-data](https://github.com/iamcal/emoji-data)
+//! checkout with [`emoji::read_catalog`]. Each vendor is backed by a [`Provider`] which renders
+//! an [`EmojiImage`] to all requested sizes and hands the result to an [`Optimizer`].
+
+use cache::Cache;
+use indicatif::{ParallelProgressIterator, ProgressStyle};
+use manifest::OutputFile;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+pub mod apple;
+pub mod atlas;
+pub mod avif;
+pub mod cache;
+pub mod cbdt;
+pub mod config;
+pub mod css;
+pub mod directories;
+pub mod emoji;
+pub mod emojidatapngs;
+pub mod error;
+pub mod fallback;
+pub mod fit;
+pub mod fontbuild;
+pub mod jxl;
+pub mod manifest;
+pub mod notoemoji;
+pub mod optimize;
+pub mod options;
+pub mod provider;
+pub mod raster;
+pub mod report;
+pub mod resize;
+pub mod shaping;
+pub mod size;
+pub mod twemoji;
+pub mod vectorfont;
+pub mod vendor;
+pub mod webp;
+
+pub use config::Config;
+pub use directories::Directories;
+pub use emoji::{Emoji, EmojiImage};
+pub use error::Error;
+pub use manifest::Manifest;
+pub use optimize::Optimizer;
+pub use options::{Options, Output};
+pub use provider::{ImageProvider, Provider, SvgProvider};
+pub use size::Size;
+pub use vendor::Vendor;
+
+/// Transforms all `emojis` with `provider` in parallel.
+///
+/// Each image is rendered once per size and written to all [`Options::outputs`].
+/// If [`Options::cache`] is set, emojis whose source didn't change since the last run are skipped.
+/// Returns a [`Manifest`] describing all files (including the skipped ones) and failures.
+/// Failures can be summarized with [`report::Report`]. Missing emojis that [`Options::vendor`]
+/// never drew are recorded in [`Manifest::unavailable`] instead of as failures.
+pub fn transform_for(
+    provider: &impl Provider,
+    options: &Options,
+    emojis: &[EmojiImage],
+    sizes: &[Size],
+) -> Manifest {
+    // atlases need every emoji to be rendered
+    let mut cache = (options.cache && options.atlas.is_none())
+        .then(|| Cache::load(options.cache_file(), options.fingerprint()));
+
+    let it = emojis
+        .par_iter()
+        .progress_with_style(ProgressStyle::with_template("{bar} {pos:>7}/{len:7} {eta}").unwrap())
+        .map(|it| {
+            (
+                it,
+                transform_cached(provider, options, cache.as_ref(), it, sizes),
+            )
+        })
+        .collect_vec_list();
+
+    let mut manifest = Manifest::default();
+    let mut skipped = 0;
+    for (emoji, result) in it.into_iter().flatten() {
+        match result {
+            Ok(transformed) => {
+                if transformed.skipped {
+                    skipped += 1;
+                }
+                if let (Some(cache), Some(source)) = (&mut cache, transformed.source) {
+                    cache.insert(emoji, source, transformed.outputs.clone());
+                }
+                manifest.push(emoji, transformed.outputs);
+            }
+            Err(e) => {
+                if let Some(ref mut cache) = cache {
+                    cache.remove(emoji);
+                }
+                let expected = options
+                    .vendor
+                    .as_ref()
+                    .is_some_and(|vendor| !emoji.has_img.has(vendor));
+                if expected && e.is_missing() {
+                    manifest.push_unavailable(emoji);
+                } else {
+                    manifest.push_failure(emoji, &e);
+                }
+            }
+        }
+    }
+
+    if let Some(cache) = cache {
+        if skipped > 0 {
+            println!("{skipped} emojis were up to date");
+        }
+        if let Err(e) = cache.save() {
+            eprintln!("Failed to write cache: {e}");
+        }
+    }
+
+    manifest
+}
+
+struct Transformed {
+    /// Hash of the source (if caching is enabled)
+    source: Option<String>,
+    /// All outputs of the emoji, including the ones from the cache
+    outputs: Vec<OutputFile>,
+    /// All outputs were up to date
+    skipped: bool,
+}
+
+fn transform_cached(
+    provider: &impl Provider,
+    options: &Options,
+    cache: Option<&Cache>,
+    emoji: &EmojiImage,
+    sizes: &[Size],
+) -> Result<Transformed, Error> {
+    let Some(cache) = cache else {
+        return Ok(Transformed {
+            source: None,
+            outputs: provider.transform(options, emoji, sizes)?,
+            skipped: false,
+        });
+    };
+
+    let source = cache::hash(&provider.read_source(emoji)?);
+    let cached: Vec<&OutputFile> = cache
+        .outputs(emoji, &source)
+        .iter()
+        .filter(|it| sizes.contains(&it.size) && options.is_output(emoji, it))
+        .collect();
+    // a size is only up to date if the files of all outputs exist
+    let (fresh, stale): (Vec<Size>, Vec<Size>) = sizes.iter().partition(|&&size| {
+        cached.iter().filter(|it| it.size == size).count() >= options.outputs().len()
+    });
+    let mut outputs: Vec<OutputFile> = cached
+        .into_iter()
+        .filter(|it| fresh.contains(&it.size))
+        .cloned()
+        .collect();
+
+    let skipped = stale.is_empty();
+    if !skipped {
+        outputs.extend(provider.transform(options, emoji, &stale)?);
+    }
+    Ok(Transformed {
+        source: Some(source),
+        outputs,
+        skipped,
+    })
+}
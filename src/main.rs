@@ -1,96 +1,92 @@
-use std::{collections::HashMap, fmt::Display, iter, path::PathBuf};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
 
-use apple::AppleFont;
 use clap::Parser;
-use directories::Directories;
-use emojidatapngs::EmojiDataPngs;
-use indicatif::{ParallelProgressIterator, ProgressStyle};
-use notoemoji::Notoemoji;
-use optimize::{Optimizer, Oxipng, Zopflipng};
-use provider::Provider;
-use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
-
-mod apple;
-mod directories;
-mod emojidatapngs;
-mod error;
-mod notoemoji;
-mod optimize;
-mod options;
-mod provider;
-mod resize;
-mod twemoji;
-mod webp;
-
-pub use error::Error;
-pub use options::Options;
-use twemoji::Twemoji;
-use webp::Webp;
-
-#[derive(serde::Deserialize)]
-struct Emoji {
-    pub short_name: String,
-    #[serde(deserialize_with = "deser_lower")]
-    pub unified: String,
-    #[serde(deserialize_with = "deser_lower_opt")]
-    pub non_qualified: Option<String>,
-    #[serde(default)]
-    pub skin_variations: HashMap<String, SkinVariation>,
-}
-
-#[derive(serde::Deserialize)]
-struct SkinVariation {
-    #[serde(deserialize_with = "deser_lower_opt")]
-    pub non_qualified: Option<String>,
-    #[serde(deserialize_with = "deser_lower")]
-    pub unified: String,
-}
-
-pub struct EmojiImage<'a> {
-    pub unified: &'a str,
-    pub non_qualified: Option<&'a str>,
-    pub short_name: &'a str,
-}
-
-fn transform_for(
-    provider: &impl Provider,
-    options: &Options,
-    emojis: &[EmojiImage],
-    sizes: &[u32],
-) {
-    let it = emojis
-        .par_iter()
-        .progress_with_style(ProgressStyle::with_template("{bar} {pos:>7}/{len:7} {eta}").unwrap())
-        .filter_map(|it| match provider.transform(options, it, sizes) {
-            Err(e) => Some(format!(
-                ":{}: ({}) failed: {}",
-                it.short_name, it.unified, e
-            )),
-            Ok(_) => None,
-        })
-        .collect_vec_list();
-    for e in it.into_iter().flatten() {
-        eprintln!("{e}");
-    }
-}
+use emojicrunch::{
+    Config, Directories, Manifest, Options, Output, Provider, Size, Vendor,
+    apple::AppleFont,
+    atlas::Atlas,
+    cbdt::CbdtFont,
+    config::{
+        AtlasSettings, FitMode, FontFormat, Format, Padding, ResizeFilter, StrikeSelection, Target,
+        WebpSettings,
+    },
+    css,
+    directories::Template,
+    emoji::{self, Version},
+    emojidatapngs::EmojiDataPngs,
+    error::ErrorKind,
+    fallback::Fallback,
+    fontbuild, manifest,
+    notoemoji::Notoemoji,
+    report::Report,
+    size::DensityNaming,
+    transform_for,
+    twemoji::Twemoji,
+    vectorfont::VectorFont,
+};
 
 #[derive(clap::Parser)]
 #[command(name = "emojicrunch", about = "Resize and render emojis")]
 struct Args {
+    /// Path to a build configuration (TOML or JSON)
+    ///
+    /// Options passed on the command line override the ones in the config for all targets.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
     /// Sets the path to the Apple font
     ///
     /// If set, emojis will be extracted from that font.
     #[arg(long, value_name = "PATH")]
     apple_font: Option<PathBuf>,
 
-    #[arg(short, default_value = "build")]
-    output_dir: PathBuf,
+    /// Render a vendor from a color font (COLR, SVG, sbix, or CBDT). Can be repeated.
+    ///
+    /// Example: --vendor-font google=Noto-COLRv1.ttf
+    #[arg(long, value_name = "VENDOR=PATH", value_parser = parse_vendor_font)]
+    vendor_font: Vec<(Vendor, PathBuf)>,
+
+    /// Index of the face in font collections (.ttc) [default: 0]
+    #[arg(long, value_name = "INDEX")]
+    font_index: Option<u32>,
+
+    /// Take emojis missing from a vendor from other vendors, in order. Can be repeated.
+    ///
+    /// Example: --fallback facebook=twitter,google
+    #[arg(long, value_name = "VENDOR=VENDORS", value_parser = parse_fallback)]
+    fallback: Vec<(Vendor, Vec<Vendor>)>,
+
+    /// Which strike of bitmap fonts (sbix, CBDT) to use for each size [default: nearest]
+    #[arg(long, value_enum, value_name = "STRIKE")]
+    font_strike: Option<StrikeSelection>,
+
+    /// Output directory [default: build]
+    #[arg(short)]
+    output_dir: Option<PathBuf>,
+
+    /// Path template of the images relative to the output directory
+    ///
+    /// Available placeholders: {vendor}, {size}, {density}, {unified}, {non_qualified},
+    /// {short_name}, {skin_tone}, and {ext}. Defaults to "{vendor}/{size}/{unified}.{ext}".
+    #[arg(long, value_name = "TEMPLATE", value_parser = parse_template)]
+    layout: Option<Template>,
 
-    /// Root directory of the emoji-data repository.
+    /// Root directory of the emoji-data repository [default: emoji-data]
     ///
     /// Submodules must be checked out
-    #[arg(long, default_value = "emoji-data")]
-    emoji_data_root: PathBuf,
+    #[arg(long)]
+    emoji_data_root: Option<PathBuf>,
+
+    /// The output format(s). Accepts multiple arguments.
+    ///
+    /// Each image is rendered once and encoded to all formats. The flags below add to this list.
+    /// Formats must have different extensions (e.g. not oxipng and zopflipng).
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "FORMATS")]
+    format: Vec<Format>,
 
     /// Use zopfli to compress the PNGs
     ///
@@ -102,180 +98,473 @@ struct Args {
     #[arg(long, default_value = "false")]
     webp: bool,
 
+    /// Encode WEBPs lossily with this quality (0-100)
+    #[arg(long, value_name = "QUALITY")]
+    webp_quality: Option<f32>,
+
+    /// Preprocess lossless WEBPs to compress better (0 = most lossy, 100 = off)
+    #[arg(long, value_name = "LEVEL")]
+    webp_near_lossless: Option<i32>,
+
+    /// Quality of the alpha channel of lossy WEBPs (0-100)
+    #[arg(long, value_name = "QUALITY")]
+    webp_alpha_quality: Option<i32>,
+
+    /// Filtering of the alpha channel of lossy WEBPs (0 = none, 1 = fast, 2 = best)
+    #[arg(long, value_name = "LEVEL")]
+    webp_alpha_filtering: Option<i32>,
+
+    /// Preserve the color of transparent pixels in WEBPs
+    #[arg(long, default_value = "false")]
+    webp_exact: bool,
+
+    /// Quality/speed trade-off of the WEBP encoder (0 = fast, 6 = slower but better)
+    #[arg(long, value_name = "METHOD")]
+    webp_method: Option<i32>,
+
+    /// Produce AVIFs
+    #[arg(long, default_value = "false")]
+    avif: bool,
+
+    /// Produce JPEG XLs
+    #[arg(long, default_value = "false")]
+    jxl: bool,
+
+    /// How sources are scaled into the output [default: contain]
+    #[arg(long, value_enum, value_name = "MODE")]
+    fit: Option<FitMode>,
+
+    /// Transparent space around each emoji in pixels (e.g. 2) or percent of the size (e.g. 10%)
+    #[arg(long, value_name = "PADDING")]
+    padding: Option<Padding>,
+
+    /// Remove transparent borders of the sources before fitting them
+    #[arg(long, default_value = "false")]
+    trim: bool,
+
+    /// Resampling filter for raster sources [default: lanczos3]
+    ///
+    /// "auto" picks a filter based on the scale factor.
+    #[arg(long, value_enum, value_name = "FILTER")]
+    resize_filter: Option<ResizeFilter>,
+
+    /// Resize raster sources in linear light (gamma-correct)
+    #[arg(long, default_value = "false")]
+    resize_linear: bool,
+
     /// The size(s) to scale/render to. Accepts multiple arguments.
     ///
-    /// Defaults to [22, 22 * 2, 22 * 4]
+    /// Sizes are either square (22), WIDTHxHEIGHT (28x22), or only a height (x22) to keep the
+    /// aspect ratio of the source. Defaults to [22, 22 * 2, 22 * 4]
     #[arg(long)]
-    size: Vec<u32>,
+    size: Vec<Size>,
+
+    /// Derive the sizes from this size at 1x and the densities [default: 22]
+    #[arg(long, value_name = "SIZE")]
+    density_base: Option<Size>,
+
+    /// Scale the base size by these densities (e.g. 1,1.5,2,3,4). Replaces --size.
+    #[arg(long, value_delimiter = ',', value_name = "SCALES")]
+    densities: Vec<f32>,
+
+    /// How densities are named in the {density} placeholder [default: ios]
+    ///
+    /// "ios" uses @2x suffixes, "android" uses the mdpi/hdpi/xhdpi/... buckets.
+    #[arg(long, value_enum, value_name = "NAMING")]
+    density_naming: Option<DensityNaming>,
+
+    /// Always render all emojis, even if they're up to date
+    #[arg(long, default_value = "false")]
+    no_cache: bool,
+
+    /// Pack the emojis of each vendor and size into atlases (sprite sheets)
+    #[arg(long, default_value = "false")]
+    atlas: bool,
+
+    /// Write a CSS stylesheet for every size (implies --atlas)
+    #[arg(long, default_value = "false")]
+    css: bool,
+
+    /// Build a color emoji font with these bitmap tables from the PNGs
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    font: Option<FontFormat>,
+
+    /// Exit with an error if more than this many emojis are missing for a vendor
+    #[arg(long, value_name = "N")]
+    max_missing: Option<usize>,
+
+    /// Exit with an error if any emoji failed with one of these kinds (except missing ones)
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "KINDS")]
+    fail_on: Vec<ErrorKind>,
+
+    /// Write a JSON report of all failures to this path
+    #[arg(long, value_name = "PATH")]
+    failure_report: Option<PathBuf>,
+
+    /// Only build emojis added in this Emoji version or later (e.g. 15.0)
+    #[arg(long, value_name = "VERSION")]
+    since: Option<Version>,
+
+    /// Only build emojis of these categories or subcategories (e.g. Flags or face-smiling)
+    #[arg(long, value_delimiter = ',', value_name = "CATEGORIES")]
+    category: Vec<String>,
+
+    /// Don't build skin variations
+    #[arg(long, default_value = "false")]
+    exclude_skin_tones: bool,
+
+    /// Only build these emojis (unified codepoints, e.g. 1f600,1f44d) and their skin variations
+    #[arg(long, value_delimiter = ',', value_name = "CODEPOINTS")]
+    only: Vec<String>,
 
     /// List of vendors to build for
     #[arg(value_enum)]
     vendors: Vec<Vendor>,
 }
 
-#[derive(Clone, PartialEq, Eq, clap::ValueEnum)]
-enum Vendor {
-    Apple,
-    Twitter,
-    Google,
-    Facebook,
-}
+impl Args {
+    fn apply(&self, config: &mut Config) {
+        if let Some(ref root) = self.emoji_data_root {
+            config.emoji_data_root = root.clone();
+        }
+        if let Some(ref font) = self.apple_font {
+            config.apple_font = Some(font.clone());
+        }
+        for (vendor, font) in &self.vendor_font {
+            config.vendor_fonts.insert(vendor.clone(), font.clone());
+        }
+        for (vendor, fallbacks) in &self.fallback {
+            config.fallbacks.insert(vendor.clone(), fallbacks.clone());
+        }
+        if let Some(index) = self.font_index {
+            config.font_index = index;
+        }
+        if let Some(strike) = self.font_strike {
+            config.font_strike = strike;
+        }
+        if let Some(max) = self.max_missing {
+            config.failures.max_missing = Some(max);
+        }
+        if !self.fail_on.is_empty() {
+            config.failures.fail_on = self.fail_on.clone();
+        }
+        if let Some(ref path) = self.failure_report {
+            config.failures.report = Some(path.clone());
+        }
+        for target in &mut config.targets {
+            self.apply_target(target);
+        }
+    }
+
+    fn apply_target(&self, target: &mut Target) {
+        if let Some(ref dir) = self.output_dir {
+            target.output_dir = dir.clone();
+        }
+        if let Some(ref layout) = self.layout {
+            target.layout = layout.clone();
+            target.vendor_layouts.clear();
+        }
+        let mut formats = self.format.clone();
+        for (enabled, format) in [
+            (self.use_zopfli, Format::Zopflipng),
+            (self.webp, Format::Webp),
+            (self.avif, Format::Avif),
+            (self.jxl, Format::Jxl),
+        ] {
+            if enabled && !formats.contains(&format) {
+                formats.push(format);
+            }
+        }
+        if !formats.is_empty() {
+            target.formats = formats;
+        }
+        self.apply_webp(&mut target.settings.webp);
+        if let Some(mode) = self.fit {
+            target.settings.fit.mode = mode;
+        }
+        if let Some(padding) = self.padding {
+            target.settings.fit.padding = padding;
+        }
+        if self.trim {
+            target.settings.fit.trim = true;
+        }
+        if let Some(filter) = self.resize_filter {
+            target.settings.resize.filter = filter;
+        }
+        if self.resize_linear {
+            target.settings.resize.linear = true;
+        }
+        if !self.size.is_empty() {
+            target.sizes = self.size.clone();
+            target.densities = None;
+        }
+        self.apply_densities(target);
+        if self.no_cache {
+            target.cache = false;
+        }
+        if self.atlas && target.atlas.is_none() {
+            target.atlas = Some(AtlasSettings::default());
+        }
+        if self.css {
+            target.css = true;
+        }
+        if let Some(format) = self.font {
+            target.font.get_or_insert_with(Default::default).format = format;
+        }
+        if let Some(since) = self.since {
+            target.filter.since = Some(since);
+        }
+        if !self.category.is_empty() {
+            target.filter.categories = self.category.clone();
+        }
+        if self.exclude_skin_tones {
+            target.filter.exclude_skin_tones = true;
+        }
+        if !self.only.is_empty() {
+            target.filter.only = self.only.clone();
+        }
+        if !self.vendors.is_empty() {
+            target.vendors = self.vendors.clone();
+        }
+        target.vendors.dedup();
+        target.sizes.dedup();
+    }
 
-impl Display for Vendor {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Vendor::Apple => write!(f, "apple"),
-            Vendor::Twitter => write!(f, "twitter"),
-            Vendor::Google => write!(f, "google"),
-            Vendor::Facebook => write!(f, "facebook"),
+    fn apply_densities(&self, target: &mut Target) {
+        if self.density_base.is_none() && self.densities.is_empty() && self.density_naming.is_none()
+        {
+            return;
+        }
+        let densities = target.densities.get_or_insert_with(Default::default);
+        if let Some(base) = self.density_base {
+            densities.base = base;
+        }
+        if !self.densities.is_empty() {
+            densities.scales = self.densities.clone();
+        }
+        if let Some(naming) = self.density_naming {
+            densities.naming = naming;
+        }
+    }
+
+    fn apply_webp(&self, webp: &mut WebpSettings) {
+        if let Some(quality) = self.webp_quality {
+            webp.lossless = false;
+            webp.quality = quality;
+        }
+        if let Some(level) = self.webp_near_lossless {
+            webp.near_lossless = level;
+        }
+        if let Some(quality) = self.webp_alpha_quality {
+            webp.alpha_quality = quality;
+        }
+        if let Some(level) = self.webp_alpha_filtering {
+            webp.alpha_filtering = level;
+        }
+        if self.webp_exact {
+            webp.exact = true;
+        }
+        if let Some(method) = self.webp_method {
+            webp.method = method;
         }
     }
 }
 
-fn main() {
-    let mut args = Args::parse();
-    args.vendors.dedup();
-    args.size.dedup();
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let mut config = match args.config {
+        Some(ref path) => Config::read(path).unwrap(),
+        None => Config::default(),
+    };
+    args.apply(&mut config);
+    if let Err(e) = config.validate() {
+        eprintln!("error: {e}");
+        return ExitCode::FAILURE;
+    }
 
-    if args.size.is_empty() {
-        args.size = vec![22, 22 * 2, 22 * 4];
+    println!("Reading emoji.json");
+    let emojis = emoji::read_catalog(&config.emoji_data_root).unwrap();
+    let images = emoji::images(&emojis);
+
+    let mut manifests: BTreeMap<&Path, BTreeMap<String, Manifest>> = BTreeMap::new();
+    for target in &config.targets {
+        let outputs = manifests.entry(&target.output_dir).or_default();
+        for (vendor, manifest) in build_target(&config, target, &images) {
+            outputs.entry(vendor).or_default().merge(manifest);
+        }
     }
-    if args.vendors.is_empty() {
-        args.vendors = vec![
-            Vendor::Apple,
-            Vendor::Twitter,
-            Vendor::Google,
-            Vendor::Facebook,
-        ];
+
+    let mut report = Report::default();
+    for (root, manifests) in &manifests {
+        manifest::write_all(root, manifests).unwrap();
+        for (vendor, manifest) in manifests {
+            report.add(vendor, manifest);
+        }
     }
 
-    println!("Reading emoji.json");
-    let emojis: Vec<Emoji> =
-        serde_json::from_slice(&fs_err::read(args.emoji_data_root.join("emoji.json")).unwrap())
-            .unwrap();
-    let images: Vec<EmojiImage<'_>> = emojis
-        .iter()
-        .flat_map(|it| {
-            iter::once(EmojiImage {
-                short_name: &it.short_name,
-                non_qualified: it.non_qualified.as_deref(),
-                unified: &it.unified,
-            })
-            .chain(it.skin_variations.values().map(|v| EmojiImage {
-                short_name: &it.short_name,
-                non_qualified: v.non_qualified.as_deref(),
-                unified: &v.unified,
-            }))
-        })
-        .collect();
+    report.print_failures();
+    println!();
+    report.print_summary();
+    if let Some(ref path) = config.failures.report {
+        report.write(path).unwrap();
+    }
 
-    let optimizer: Box<dyn Optimizer> = if args.webp {
-        Box::new(Webp)
-    } else if args.use_zopfli {
-        Box::new(Zopflipng)
+    let violations = report.violations(&config.failures);
+    if violations.is_empty() {
+        ExitCode::SUCCESS
     } else {
-        Box::new(Oxipng)
-    };
-    let extension = if args.webp { "webp" } else { "png" };
-
-    for vendor in args.vendors {
-        let name = vendor.to_string();
-        println!("Processing {name}...");
-
-        let directories = Directories::for_provider("build", &name, extension);
-        directories.create_sizes(&args.size).unwrap();
-        let options = Options::new(directories, optimizer.as_ref());
-
-        match vendor {
-            Vendor::Apple => match args.apple_font {
-                Some(ref font) => {
-                    let font = std::fs::read(font).unwrap();
-                    let apple = AppleFont::new(&font, 0).unwrap();
-                    transform_for(&apple, &options, &images, &args.size);
-                }
-                None => {
-                    let root = args.emoji_data_root.join("img-apple-160");
-                    let apple = EmojiDataPngs::new(&root);
-                    transform_for(&apple, &options, &images, &args.size);
-                }
-            },
-            Vendor::Twitter => {
-                let root = args
-                    .emoji_data_root
-                    .join("build/twitter/twemoji/assets/svg");
-                let twemoji = Twemoji::new(&root);
-                transform_for(&twemoji, &options, &images, &args.size);
-            }
-            Vendor::Google => {
-                let root = args.emoji_data_root.join("build/google/noto-emoji/svg");
-                let noto = Notoemoji::new(&root);
-                transform_for(&noto, &options, &images, &args.size);
-            }
-            Vendor::Facebook => {
-                let root = args.emoji_data_root.join("img-facebook-96");
-                let facebook = EmojiDataPngs::new(&root);
-                transform_for(&facebook, &options, &images, &args.size);
-            }
+        for violation in violations {
+            eprintln!("error: {violation}");
         }
+        ExitCode::FAILURE
     }
 }
 
-fn deser_lower<'de, D>(d: D) -> Result<String, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    struct Visitor;
-    impl serde::de::Visitor<'_> for Visitor {
-        type Value = String;
+fn parse_template(s: &str) -> Result<Template, String> {
+    s.parse().map_err(|e: emojicrunch::Error| e.to_string())
+}
+
+fn parse_vendor_font(s: &str) -> Result<(Vendor, PathBuf), String> {
+    let (vendor, path) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected VENDOR=PATH, got '{s}'"))?;
+    let vendor = <Vendor as clap::ValueEnum>::from_str(vendor, true)?;
+    Ok((vendor, PathBuf::from(path)))
+}
 
-        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-            write!(f, "a string")
-        }
+fn parse_fallback(s: &str) -> Result<(Vendor, Vec<Vendor>), String> {
+    let (vendor, fallbacks) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected VENDOR=VENDORS, got '{s}'"))?;
+    let vendor = <Vendor as clap::ValueEnum>::from_str(vendor, true)?;
+    let fallbacks = fallbacks
+        .split(',')
+        .map(|it| <Vendor as clap::ValueEnum>::from_str(it, true))
+        .collect::<Result<_, _>>()?;
+    Ok((vendor, fallbacks))
+}
 
-        fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
-        where
-            E: serde::de::Error,
-        {
-            Ok(v.to_ascii_lowercase())
+/// Where the emojis of a vendor are read from (the providers borrow from this).
+enum Source {
+    Font(PathBuf, Vec<u8>),
+    Dir(PathBuf),
+}
+
+impl Source {
+    fn for_vendor(config: &Config, vendor: &Vendor) -> Self {
+        if let Some(font) = config.font_for(vendor) {
+            return Source::Font(font.to_owned(), std::fs::read(font).unwrap());
         }
+        let root = &config.emoji_data_root;
+        Source::Dir(match vendor {
+            Vendor::Apple => root.join("img-apple-160"),
+            Vendor::Twitter => root.join("build/twitter/twemoji/assets/svg"),
+            Vendor::Google => root.join("build/google/noto-emoji/svg"),
+            Vendor::Facebook => root.join("img-facebook-96"),
+        })
     }
 
-    d.deserialize_str(Visitor)
+    fn provider<'a>(&'a self, config: &Config, vendor: &Vendor) -> Box<dyn Provider + 'a> {
+        match (self, vendor) {
+            (Source::Font(path, data), _) => font_provider(config, path, data),
+            (Source::Dir(dir), Vendor::Apple | Vendor::Facebook) => {
+                Box::new(EmojiDataPngs::new(dir))
+            }
+            (Source::Dir(dir), Vendor::Twitter) => Box::new(Twemoji::new(dir)),
+            (Source::Dir(dir), Vendor::Google) => Box::new(Notoemoji::new(dir)),
+        }
+    }
 }
 
-fn deser_lower_opt<'de, D>(d: D) -> Result<Option<String>, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    struct Visitor;
-    impl<'deinner> serde::de::Visitor<'deinner> for Visitor {
-        type Value = Option<String>;
+/// Renders emojis from a vector (COLR/SVG) or bitmap (sbix/CBDT) color font.
+fn font_provider<'a>(config: &Config, path: &Path, data: &'a [u8]) -> Box<dyn Provider + 'a> {
+    let (index, strikes) = (config.font_index, config.font_strike);
+    if let Some(font) = VectorFont::new(data, index) {
+        return Box::new(font);
+    }
+    if let Some(font) = AppleFont::new(data, index, strikes) {
+        return Box::new(font);
+    }
+    let font = CbdtFont::new(data, index, strikes)
+        .unwrap_or_else(|| panic!("{} has no supported color glyphs", path.display()));
+    Box::new(font)
+}
 
-        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-            write!(f, "a string")
-        }
+fn build_target(
+    config: &Config,
+    target: &Target,
+    images: &[emoji::EmojiImage],
+) -> BTreeMap<String, Manifest> {
+    let mut manifests = BTreeMap::new();
+    let optimizers: Vec<_> = target
+        .formats
+        .iter()
+        .map(|format| (format.extension(), format.optimizer()))
+        .collect();
+    let extensions: Vec<_> = optimizers.iter().map(|(ext, _)| *ext).collect();
+    let sizes = target.output_sizes().expect("the target was validated");
+    let filtered: Vec<_>;
+    let images: &[emoji::EmojiImage] = if target.filter.is_empty() {
+        images
+    } else {
+        filtered = images
+            .iter()
+            .filter(|it| it.matches(&target.filter))
+            .copied()
+            .collect();
+        println!(
+            "{} of {} emojis match the filter",
+            filtered.len(),
+            images.len()
+        );
+        &filtered
+    };
 
-        fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
-        where
-            D: serde::Deserializer<'deinner>,
-        {
-            deserializer.deserialize_str(Visitor)
+    for vendor in &target.vendors {
+        let name = vendor.to_string();
+        println!("Processing {name} ({})...", extensions.join(", "));
+
+        let outputs = optimizers
+            .iter()
+            .map(|(extension, optimizer)| Output {
+                directories: Directories::new(
+                    &target.output_dir,
+                    &name,
+                    *extension,
+                    target.layout_for(vendor).clone(),
+                ),
+                optimizer: optimizer.as_ref(),
+            })
+            .collect();
+        let mut options = Options::with_outputs(outputs, &target.settings);
+        options.cache = target.cache;
+        options.atlas = target.atlas_settings().map(Atlas::new);
+        options.vendor = Some(vendor.clone());
+
+        let fallbacks = config.fallbacks_for(vendor);
+        let source = Source::for_vendor(config, vendor);
+        let fallback_sources: Vec<_> = fallbacks
+            .iter()
+            .map(|it| Source::for_vendor(config, it))
+            .collect();
+        let mut provider = Fallback::new(source.provider(config, vendor));
+        for (fallback, source) in fallbacks.iter().zip(&fallback_sources) {
+            provider = provider.or(fallback.clone(), source.provider(config, fallback));
         }
-
-        fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
-        where
-            E: serde::de::Error,
-        {
-            Ok(Some(v.to_ascii_lowercase()))
+        let manifest = transform_for(&provider, &options, images, &sizes);
+        if let Some(ref atlas) = options.atlas {
+            let indices = atlas.write(&options).unwrap();
+            if target.css {
+                css::write(&options, &indices, atlas.settings().padding).unwrap();
+            }
         }
-
-        fn visit_none<E>(self) -> Result<Self::Value, E>
-        where
-            E: serde::de::Error,
-        {
-            Ok(None)
+        if let Some(ref font) = target.font {
+            match fontbuild::write(&target.output_dir, &name, &manifest, font) {
+                Ok(path) => println!("Wrote {}", path.display()),
+                Err(e) => eprintln!("Failed to build the {name} font: {e}"),
+            }
         }
+        manifests.insert(name, manifest);
     }
-
-    d.deserialize_option(Visitor)
+    manifests
 }
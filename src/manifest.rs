@@ -0,0 +1,175 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use crate::{EmojiImage, Error, Size, Vendor, cache, error::ErrorKind};
+
+/// Describes all files produced for a vendor.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct Manifest {
+    pub emojis: Vec<Entry>,
+    pub failures: Vec<Failure>,
+    /// Missing emojis the vendor has no image for according to emoji-data (not failures)
+    pub unavailable: Vec<EmojiInfo>,
+}
+
+/// An emoji (or one of its skin variations) and all its produced files.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Entry {
+    #[serde(flatten)]
+    pub emoji: EmojiInfo,
+    pub files: Vec<OutputFile>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Failure {
+    #[serde(flatten)]
+    pub emoji: EmojiInfo,
+    pub kind: ErrorKind,
+    /// The emoji isn't available from the vendor
+    pub missing: bool,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct EmojiInfo {
+    pub unified: String,
+    pub non_qualified: Option<String>,
+    pub short_name: String,
+    pub skin_tone: Option<String>,
+}
+
+/// A single file written to disk.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct OutputFile {
+    /// The requested size
+    pub size: Size,
+    /// Path relative to the output directory
+    pub path: PathBuf,
+    pub format: String,
+    pub width: u32,
+    pub height: u32,
+    pub bytes: u64,
+    /// BLAKE3 hash of the file contents
+    pub blake3: String,
+    /// The vendor that supplied the image if the emoji is missing from the manifest's vendor
+    /// (see [`crate::fallback::Fallback`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vendor: Option<Vendor>,
+}
+
+impl Manifest {
+    pub fn push(&mut self, emoji: &EmojiImage, files: Vec<OutputFile>) {
+        self.emojis.push(Entry {
+            emoji: emoji.into(),
+            files,
+        });
+    }
+
+    pub fn push_failure(&mut self, emoji: &EmojiImage, error: &Error) {
+        self.failures.push(Failure {
+            emoji: emoji.into(),
+            kind: error.kind(),
+            missing: error.is_missing(),
+            error: error.to_string(),
+        });
+    }
+
+    pub fn push_unavailable(&mut self, emoji: &EmojiImage) {
+        self.unavailable.push(emoji.into());
+    }
+
+    /// Merges `other` (e.g. the same vendor with another format) into this manifest.
+    pub fn merge(&mut self, other: Manifest) {
+        let mut indices: HashMap<String, usize> = self
+            .emojis
+            .iter()
+            .enumerate()
+            .map(|(i, it)| (it.emoji.unified.clone(), i))
+            .collect();
+        for entry in other.emojis {
+            match indices.get(&entry.emoji.unified) {
+                Some(&i) => self.emojis[i].files.extend(entry.files),
+                None => {
+                    indices.insert(entry.emoji.unified.clone(), self.emojis.len());
+                    self.emojis.push(entry);
+                }
+            }
+        }
+        self.failures.extend(other.failures);
+
+        let mut unavailable: HashSet<String> = self
+            .unavailable
+            .iter()
+            .map(|it| it.unified.clone())
+            .collect();
+        for emoji in other.unavailable {
+            if unavailable.insert(emoji.unified.clone()) {
+                self.unavailable.push(emoji);
+            }
+        }
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        fs_err::write(path, serde_json::to_vec_pretty(self)?).map_err(Into::into)
+    }
+}
+
+/// Writes the manifests of all vendors of an output directory.
+///
+/// Each vendor gets a `{vendor}/manifest.json`, and the combined manifest is written to
+/// `manifest.json`.
+pub fn write_all(root: &Path, manifests: &BTreeMap<String, Manifest>) -> Result<(), Error> {
+    for (vendor, manifest) in manifests {
+        let dir = root.join(vendor);
+        fs_err::create_dir_all(&dir)?;
+        manifest.write(dir.join("manifest.json"))?;
+    }
+    fs_err::write(
+        root.join("manifest.json"),
+        serde_json::to_vec_pretty(manifests)?,
+    )
+    .map_err(Into::into)
+}
+
+impl OutputFile {
+    /// Writes `data` to `root/path` and describes the written file.
+    pub fn write(
+        root: &Path,
+        path: PathBuf,
+        size: Size,
+        (width, height): (u32, u32),
+        data: &[u8],
+    ) -> Result<Self, Error> {
+        let full_path = root.join(&path);
+        if let Some(parent) = full_path.parent() {
+            fs_err::create_dir_all(parent)?;
+        }
+        fs_err::write(full_path, data)?;
+        Ok(Self {
+            size,
+            format: path
+                .extension()
+                .map(|it| it.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            path,
+            width,
+            height,
+            bytes: data.len() as u64,
+            blake3: cache::hash(data),
+            vendor: None,
+        })
+    }
+}
+
+impl From<&EmojiImage<'_>> for EmojiInfo {
+    fn from(value: &EmojiImage<'_>) -> Self {
+        Self {
+            unified: value.unified.to_owned(),
+            non_qualified: value.non_qualified.map(ToOwned::to_owned),
+            short_name: value.short_name.to_owned(),
+            skin_tone: value.skin_tone.map(str::to_ascii_lowercase),
+        }
+    }
+}
@@ -2,6 +2,7 @@ use std::path::{Path, PathBuf};
 
 use crate::{
     EmojiImage,
+    manifest::OutputFile,
     provider::{Provider, SvgProvider},
 };
 
@@ -34,6 +35,13 @@ fn is_flag(s: &str) -> bool {
 
 impl SvgProvider for Notoemoji<'_> {
     fn read_svg(&self, emoji: &EmojiImage) -> Result<usvg::Tree, crate::Error> {
+        let data = self.read_source(emoji)?;
+        usvg::Tree::from_data(&data, &self.options).map_err(Into::into)
+    }
+}
+
+impl Provider for Notoemoji<'_> {
+    fn read_source(&self, emoji: &EmojiImage) -> Result<Vec<u8>, crate::Error> {
         let file = format!(
             "emoji_u{}.svg",
             emoji.non_qualified.unwrap_or(emoji.unified)
@@ -46,18 +54,15 @@ impl SvgProvider for Notoemoji<'_> {
         }
         .join(file);
 
-        let data = fs_err::read(path)?;
-        usvg::Tree::from_data(&data, &self.options).map_err(Into::into)
+        fs_err::read(path).map_err(Into::into)
     }
-}
 
-impl Provider for Notoemoji<'_> {
     fn transform(
         &self,
         options: &crate::Options,
         emoji: &EmojiImage,
-        sizes: &[u32],
-    ) -> Result<(), crate::Error> {
+        sizes: &[crate::Size],
+    ) -> Result<Vec<OutputFile>, crate::Error> {
         self.transform_svg(options, emoji, sizes)
     }
 }
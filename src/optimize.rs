@@ -1,5 +1,3 @@
-use std::path::Path;
-
 use image::{ImageEncoder, codecs::png::PngEncoder};
 
 use crate::Error;
@@ -12,30 +10,28 @@ pub trait OptimizableImage {
     fn data(&self) -> &[u8];
 }
 
+/// Encodes and compresses images, returning the file contents.
 pub trait Optimizer: Sync {
+    /// A unique name of this optimizer, part of the cache fingerprint.
+    fn name(&self) -> &'static str;
+
     fn optimize_fir(
         &self,
         options: &crate::Options,
         image: fast_image_resize::images::Image,
-        path: &Path,
-    ) -> Result<(), Error>;
+    ) -> Result<Vec<u8>, Error>;
     fn optimize_skia(
         &self,
         options: &crate::Options,
         image: tiny_skia::Pixmap,
-        path: &Path,
-    ) -> Result<(), Error>;
+    ) -> Result<Vec<u8>, Error>;
 }
 
 pub struct Oxipng;
 pub struct Zopflipng;
 
 impl Oxipng {
-    fn optimize(
-        options: &oxipng::Options,
-        image: impl OptimizableImage,
-        path: impl AsRef<Path>,
-    ) -> Result<(), Error> {
+    fn optimize(options: &oxipng::Options, image: impl OptimizableImage) -> Result<Vec<u8>, Error> {
         let raw = oxipng::RawImage::new(
             image.width(),
             image.height(),
@@ -43,28 +39,29 @@ impl Oxipng {
             oxipng::BitDepth::Eight,
             image.into_data(),
         )?;
-        let png = raw.create_optimized_png(options)?;
-        fs_err::write(path, png).map_err(Into::into)
+        raw.create_optimized_png(options).map_err(Into::into)
     }
 }
 
 impl Optimizer for Oxipng {
+    fn name(&self) -> &'static str {
+        "oxipng"
+    }
+
     fn optimize_fir(
         &self,
         options: &crate::Options,
         image: fast_image_resize::images::Image,
-        path: &Path,
-    ) -> Result<(), Error> {
-        Self::optimize(&options.oxipng, image, path)
+    ) -> Result<Vec<u8>, Error> {
+        Self::optimize(&options.oxipng, image)
     }
 
     fn optimize_skia(
         &self,
         options: &crate::Options,
         image: tiny_skia::Pixmap,
-        path: &Path,
-    ) -> Result<(), Error> {
-        Self::optimize(&options.oxipng, image, path)
+    ) -> Result<Vec<u8>, Error> {
+        Self::optimize(&options.oxipng, image)
     }
 }
 
@@ -72,31 +69,32 @@ impl Zopflipng {
     fn optimize(
         options: &zopflipng::Options,
         image: impl OptimizableImage,
-        path: impl AsRef<Path>,
-    ) -> Result<(), Error> {
+    ) -> Result<Vec<u8>, Error> {
         let data = image.into_png();
         let out = zopflipng::optimize(&data, options).map_err(Error::Zopfli)?;
-        fs_err::write(path, out).map_err(Into::into)
+        Ok(out.as_ref().to_vec())
     }
 }
 
 impl Optimizer for Zopflipng {
+    fn name(&self) -> &'static str {
+        "zopflipng"
+    }
+
     fn optimize_fir(
         &self,
         options: &crate::Options,
         image: fast_image_resize::images::Image,
-        path: &Path,
-    ) -> Result<(), Error> {
-        Self::optimize(&options.zopfli, image, path)
+    ) -> Result<Vec<u8>, Error> {
+        Self::optimize(&options.zopfli, image)
     }
 
     fn optimize_skia(
         &self,
         options: &crate::Options,
         image: tiny_skia::Pixmap,
-        path: &Path,
-    ) -> Result<(), Error> {
-        Self::optimize(&options.zopfli, image, path)
+    ) -> Result<Vec<u8>, Error> {
+        Self::optimize(&options.zopfli, image)
     }
 }
 
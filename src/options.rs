@@ -1,29 +1,75 @@
 use std::path::PathBuf;
 
-use crate::{EmojiImage, directories::Directories, optimize::Optimizer};
+use crate::{
+    EmojiImage, Error, Size, Vendor,
+    atlas::Atlas,
+    avif, cache,
+    config::{FitSettings, JxlSettings, ResizeSettings, Settings},
+    directories::Directories,
+    manifest::OutputFile,
+    optimize::{OptimizableImage, Optimizer},
+};
 
 pub struct Options<'a> {
-    pub resize: fast_image_resize::ResizeOptions,
+    pub fit: FitSettings,
+    pub resize: ResizeSettings,
     pub oxipng: oxipng::Options,
     pub zopfli: zopflipng::Options<'static>,
-    pub optimizer: &'a dyn Optimizer,
     pub webp: libwebp_sys::WebPConfig,
-    directories: Directories,
+    pub avif: ravif::Encoder,
+    pub jxl: JxlSettings,
+    /// Skip emojis which are up to date (see [`crate::cache::Cache`])
+    ///
+    /// This has no effect if an atlas is built.
+    pub cache: bool,
+    /// Collect all rendered images into an atlas (see [`Atlas::write`])
+    pub atlas: Option<Atlas>,
+    /// The vendor the images are produced for
+    ///
+    /// If set, missing emojis the vendor has no image for (see [`crate::emoji::HasImg`]) aren't
+    /// reported as failures.
+    pub vendor: Option<Vendor>,
+    outputs: Vec<Output<'a>>,
+    fingerprint: String,
+}
+
+/// An optimizer and the location its files are written to.
+///
+/// Every rendered image is passed to all outputs of the [`Options`].
+pub struct Output<'a> {
+    pub directories: Directories,
+    pub optimizer: &'a dyn Optimizer,
 }
 
 impl<'a> Options<'a> {
-    pub fn new(directories: Directories, optimizer: &'a dyn Optimizer) -> Self {
-        let resize = fast_image_resize::ResizeOptions::new().resize_alg(
-            fast_image_resize::ResizeAlg::Convolution(fast_image_resize::FilterType::Lanczos3),
-        );
+    /// Creates options writing to a single output.
+    pub fn new(
+        directories: Directories,
+        optimizer: &'a dyn Optimizer,
+        settings: &Settings,
+    ) -> Self {
+        Self::with_outputs(
+            vec![Output {
+                directories,
+                optimizer,
+            }],
+            settings,
+        )
+    }
+
+    /// Creates options writing each image to all `outputs`.
+    ///
+    /// All outputs should share the same root and provider.
+    pub fn with_outputs(outputs: Vec<Output<'a>>, settings: &Settings) -> Self {
+        assert!(!outputs.is_empty(), "at least one output is required");
 
-        let oxipng = oxipng::Options::max_compression();
+        let oxipng = oxipng::Options::from_preset(settings.oxipng.level);
 
         let mut zopfli = zopflipng::Options::new();
         zopfli
-            .set_lossy_transparent(true)
-            .set_num_iterations(15)
-            .set_num_iterations_large(11)
+            .set_lossy_transparent(settings.zopfli.lossy_transparent)
+            .set_num_iterations(settings.zopfli.iterations)
+            .set_num_iterations_large(settings.zopfli.iterations_large)
             .set_filter_strategies(&[
                 zopflipng::STRATEGY_ZERO,
                 zopflipng::STRATEGY_PREDEFINED,
@@ -33,26 +79,157 @@ impl<'a> Options<'a> {
 
         let mut webp = libwebp_sys::WebPConfig::new_with_preset(
             libwebp_sys::WebPPreset::WEBP_PRESET_ICON,
-            100.0,
+            settings.webp.quality,
         )
         .unwrap();
-        webp.lossless = 1;
-        webp.quality = 100.0;
-        webp.method = 6;
+        webp.lossless = settings.webp.lossless as i32;
+        webp.quality = settings.webp.quality;
+        webp.near_lossless = settings.webp.near_lossless;
+        webp.alpha_quality = settings.webp.alpha_quality;
+        webp.alpha_filtering = settings.webp.alpha_filtering;
+        webp.exact = settings.webp.exact as i32;
+        webp.method = settings.webp.method;
         webp.image_hint = libwebp_sys::WebPImageHint::WEBP_HINT_GRAPH;
         webp.use_sharp_yuv = 1;
 
+        let avif = avif::encoder(&settings.avif);
+
+        let names: Vec<_> = outputs
+            .iter()
+            .map(|it| (it.optimizer.name(), it.directories.template()))
+            .collect();
+        let fingerprint = serde_json::to_vec(&(env!("CARGO_PKG_VERSION"), names, settings))
+            .map(|it| cache::hash(&it))
+            .unwrap_or_default();
+
         Self {
-            resize,
+            fit: settings.fit.clone(),
+            resize: settings.resize.clone(),
             oxipng,
             zopfli,
-            optimizer,
             webp,
-            directories,
+            avif,
+            jxl: settings.jxl.clone(),
+            cache: true,
+            atlas: None,
+            vendor: None,
+            outputs,
+            fingerprint,
         }
     }
 
-    pub fn emoji_dir(&self, size: u32, emoji: &EmojiImage) -> PathBuf {
-        self.directories.for_emoji(size, emoji)
+    /// Identifies the tool version and settings used to produce the outputs.
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    pub fn outputs(&self) -> &[Output<'a>] {
+        &self.outputs
+    }
+
+    /// Path of the cache shared by all outputs.
+    ///
+    /// This is `.cache.{ext}.json` for a single output and `.cache.{ext1}.{ext2}.json` for
+    /// multiple outputs.
+    pub fn cache_file(&self) -> PathBuf {
+        let extensions: Vec<_> = self
+            .outputs
+            .iter()
+            .map(|it| it.directories.extension())
+            .collect();
+        self.outputs[0]
+            .directories
+            .provider_dir()
+            .join(format!(".cache.{}.json", extensions.join(".")))
+    }
+
+    /// Checks if `file` was produced by one of the outputs for `emoji` and still exists.
+    pub fn is_output(&self, emoji: &EmojiImage, file: &OutputFile) -> bool {
+        self.outputs.iter().any(|it| {
+            it.directories.relative_emoji_path(file.size, emoji) == file.path
+                && it.directories.for_emoji(file.size, emoji).exists()
+        })
+    }
+
+    /// Encodes a resized image of `emoji` with all optimizers and writes the results.
+    pub fn write_fir(
+        &self,
+        size: Size,
+        emoji: &EmojiImage,
+        image: fast_image_resize::images::Image<'static>,
+    ) -> Result<Vec<OutputFile>, Error> {
+        let dimensions = (image.width(), image.height());
+        if let Some(ref atlas) = self.atlas {
+            let rgba =
+                image::RgbaImage::from_raw(dimensions.0, dimensions.1, image.buffer().to_vec())
+                    .unwrap();
+            atlas.add(size, emoji, rgba);
+            if !atlas.settings().files {
+                return Ok(Vec::new());
+            }
+        }
+        let mut image = Some(image);
+        let mut files = Vec::with_capacity(self.outputs.len());
+        for (i, output) in self.outputs.iter().enumerate() {
+            // only copy the image if another optimizer needs it
+            let image = if i + 1 == self.outputs.len() {
+                image.take().unwrap()
+            } else {
+                image.as_ref().unwrap().copy()
+            };
+            let data = output.optimizer.optimize_fir(self, image)?;
+            files.push(output.write(size, emoji, dimensions, &data)?);
+        }
+        Ok(files)
+    }
+
+    /// Encodes a rendered image of `emoji` with all optimizers and writes the results.
+    pub fn write_skia(
+        &self,
+        size: Size,
+        emoji: &EmojiImage,
+        pixmap: tiny_skia::Pixmap,
+    ) -> Result<Vec<OutputFile>, Error> {
+        let dimensions = (pixmap.width(), pixmap.height());
+        if let Some(ref atlas) = self.atlas {
+            let rgba =
+                image::RgbaImage::from_raw(dimensions.0, dimensions.1, pixmap.clone().into_data())
+                    .unwrap();
+            atlas.add(size, emoji, rgba);
+            if !atlas.settings().files {
+                return Ok(Vec::new());
+            }
+        }
+        let mut pixmap = Some(pixmap);
+        let mut files = Vec::with_capacity(self.outputs.len());
+        for (i, output) in self.outputs.iter().enumerate() {
+            let pixmap = if i + 1 == self.outputs.len() {
+                pixmap.take().unwrap()
+            } else {
+                pixmap.clone().unwrap()
+            };
+            let data = output.optimizer.optimize_skia(self, pixmap)?;
+            files.push(output.write(size, emoji, dimensions, &data)?);
+        }
+        Ok(files)
+    }
+}
+
+impl Output<'_> {
+    /// Writes the encoded image of `emoji` for `size` to its output path.
+    fn write(
+        &self,
+        size: Size,
+        emoji: &EmojiImage,
+        dimensions: (u32, u32),
+        data: &[u8],
+    ) -> Result<OutputFile, Error> {
+        OutputFile::write(
+            self.directories.root(),
+            self.directories.relative_emoji_path(size, emoji),
+            size,
+            dimensions,
+            data,
+        )
     }
 }
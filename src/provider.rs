@@ -1,10 +1,18 @@
-use usvg::Transform;
-
-use crate::{EmojiImage, Options};
-use crate::{Error, resize::resize};
+use crate::{EmojiImage, Options, Size, manifest::OutputFile};
+use crate::{Error, fit};
 
 pub trait Provider: Sync {
-    fn transform(&self, options: &Options, emoji: &EmojiImage, sizes: &[u32]) -> Result<(), Error>;
+    /// Reads the raw source asset of `emoji` (e.g. the SVG file or the glyph data).
+    ///
+    /// This is used to detect changes to the source.
+    fn read_source(&self, emoji: &EmojiImage) -> Result<Vec<u8>, Error>;
+
+    fn transform(
+        &self,
+        options: &Options,
+        emoji: &EmojiImage,
+        sizes: &[Size],
+    ) -> Result<Vec<OutputFile>, Error>;
 }
 
 pub trait ImageProvider: Sync {
@@ -14,16 +22,18 @@ pub trait ImageProvider: Sync {
         &self,
         options: &Options,
         emoji: &EmojiImage,
-        sizes: &[u32],
-    ) -> Result<(), Error> {
-        let base_image = self.read_image(emoji)?.into_rgba8();
+        sizes: &[Size],
+    ) -> Result<Vec<OutputFile>, Error> {
+        let mut base_image = self.read_image(emoji)?.into_rgba8();
+        if options.fit.trim {
+            base_image = fit::trim(base_image);
+        }
+        let mut outputs = Vec::with_capacity(sizes.len() * options.outputs().len());
         for &size in sizes {
-            let resized = resize(&options.resize, &base_image, size)?;
-            options
-                .optimizer
-                .optimize_fir(options, resized, &options.emoji_dir(size, emoji))?;
+            let resized = fit::fit_image(&options.fit, &options.resize, &base_image, size)?;
+            outputs.extend(options.write_fir(size, emoji, resized)?);
         }
-        Ok(())
+        Ok(outputs)
     }
 }
 
@@ -34,19 +44,22 @@ pub trait SvgProvider: Sync {
         &self,
         options: &Options,
         emoji: &EmojiImage,
-        sizes: &[u32],
-    ) -> Result<(), Error> {
+        sizes: &[Size],
+    ) -> Result<Vec<OutputFile>, Error> {
         let svg = self.read_svg(emoji)?;
-        let svg_size = svg.size().width().max(svg.size().height());
+        let content = if options.fit.trim {
+            svg.root().abs_layer_bounding_box().to_rect()
+        } else {
+            svg.size().to_rect(0.0, 0.0).unwrap()
+        };
+        let mut outputs = Vec::with_capacity(sizes.len() * options.outputs().len());
         for &size in sizes {
-            let mut pixmap = tiny_skia::Pixmap::new(size, size).unwrap();
-            let scale = (size as f32) / svg_size;
-            let transform = Transform::from_scale(scale, scale);
+            let dimensions = fit::dimensions(size, content.width(), content.height());
+            let mut pixmap = tiny_skia::Pixmap::new(dimensions.0, dimensions.1).unwrap();
+            let transform = fit::transform(&options.fit, content, dimensions);
             resvg::render(&svg, transform, &mut pixmap.as_mut());
-            options
-                .optimizer
-                .optimize_skia(options, pixmap, &options.emoji_dir(size, emoji))?;
+            outputs.extend(options.write_skia(size, emoji, pixmap)?);
         }
-        Ok(())
+        Ok(outputs)
     }
 }
@@ -0,0 +1,161 @@
+use std::{
+    collections::{BTreeMap, btree_map::Entry},
+    io::Cursor,
+};
+
+use rustybuzz::GlyphBuffer;
+use ttf_parser::{GlyphId, RasterGlyphImage, RasterImageFormat};
+
+use crate::{EmojiImage, Error, Options, Size, fit, manifest::OutputFile};
+
+/// Looks up the bitmap of every shaped glyph.
+///
+/// Glyphs without a bitmap (e.g. a ZWJ or VS16 left over from a sequence without a ligature)
+/// are `None`. Fails if no glyph has a bitmap.
+pub fn glyph_images<'a>(
+    glyphs: &GlyphBuffer,
+    get: impl Fn(GlyphId) -> Option<RasterGlyphImage<'a>>,
+) -> Result<Vec<Option<RasterGlyphImage<'a>>>, Error> {
+    let images: Vec<_> = glyphs
+        .glyph_infos()
+        .iter()
+        .map(|glyph| get(GlyphId(glyph.glyph_id as u16)))
+        .collect();
+    if images.iter().all(Option::is_none) {
+        return Err(Error::NoEmoji);
+    }
+    Ok(images)
+}
+
+/// The source of the glyphs for the cache.
+pub fn source(glyphs: &GlyphBuffer, images: &[Option<RasterGlyphImage>]) -> Vec<u8> {
+    let mut source = Vec::new();
+    for (glyph, image) in glyphs.glyph_infos().iter().zip(images) {
+        source.extend_from_slice(&glyph.glyph_id.to_le_bytes());
+        if let Some(image) = image {
+            source.extend_from_slice(&image.pixels_per_em.to_le_bytes());
+            source.extend_from_slice(image.data);
+        }
+    }
+    source
+}
+
+/// Renders the shaped glyphs to every size, using the bitmaps returned by `strike` for a height.
+///
+/// Every strike is only decoded once, even if it's used for multiple sizes.
+pub fn transform<'a>(
+    options: &Options,
+    emoji: &EmojiImage,
+    sizes: &[Size],
+    glyphs: &GlyphBuffer,
+    units_per_em: u16,
+    strike: impl Fn(u32) -> Result<Vec<Option<RasterGlyphImage<'a>>>, Error>,
+) -> Result<Vec<OutputFile>, Error> {
+    let mut decoded = BTreeMap::new();
+    let mut outputs = Vec::with_capacity(sizes.len() * options.outputs().len());
+    for &size in sizes {
+        let images = strike(size.height)?;
+        // glyphs missing from a strike come from another one
+        let strikes: Vec<_> = images
+            .iter()
+            .map(|it| it.as_ref().map(|it| it.pixels_per_em))
+            .collect();
+        let image = match decoded.entry(strikes) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let mut image = composite(glyphs, &images, units_per_em)?.into_rgba8();
+                if options.fit.trim {
+                    image = fit::trim(image);
+                }
+                entry.insert(image)
+            }
+        };
+        let resized = fit::fit_image(&options.fit, &options.resize, image, size)?;
+        outputs.extend(options.write_fir(size, emoji, resized)?);
+    }
+    Ok(outputs)
+}
+
+/// Decodes the glyphs and places them according to the shaping result.
+///
+/// A single bitmap is returned as is. Multiple bitmaps (e.g. a ZWJ sequence without a
+/// ligature) are positioned with their advances, offsets, and bitmap origins on a square canvas.
+/// Glyphs without a bitmap only advance the pen.
+pub fn composite(
+    glyphs: &GlyphBuffer,
+    images: &[Option<RasterGlyphImage>],
+    units_per_em: u16,
+) -> Result<image::DynamicImage, Error> {
+    let mut bitmaps = images.iter().flatten();
+    match (bitmaps.next(), bitmaps.next()) {
+        (None, _) => return Err(Error::NoEmoji),
+        (Some(image), None) => return decode(image),
+        _ => (),
+    }
+
+    // bitmaps in pixels with y pointing up from the baseline
+    let mut placed = Vec::with_capacity(images.len());
+    let mut pen = 0;
+    for (position, image) in glyphs.glyph_positions().iter().zip(images) {
+        if let Some(image) = image {
+            let decoded = decode(image)?;
+            let scale = image.pixels_per_em as f32 / units_per_em as f32;
+            let left = (pen + position.x_offset) as f32 * scale + image.x as f32;
+            let bottom = position.y_offset as f32 * scale + image.y as f32;
+            placed.push((left, bottom + decoded.height() as f32, decoded));
+        }
+        pen += position.x_advance;
+    }
+
+    let (mut min_x, mut max_x) = (f32::MAX, f32::MIN);
+    let (mut min_y, mut max_y) = (f32::MAX, f32::MIN);
+    for (left, top, decoded) in &placed {
+        min_x = min_x.min(*left);
+        max_x = max_x.max(left + decoded.width() as f32);
+        min_y = min_y.min(top - decoded.height() as f32);
+        max_y = max_y.max(*top);
+    }
+
+    let width = (max_x - min_x).ceil();
+    let height = (max_y - min_y).ceil();
+    let side = width.max(height);
+    // center the content so resizing to a square doesn't distort it
+    let offset_x = (side - width) / 2.0 - min_x;
+    let offset_y = (side - height) / 2.0 + max_y;
+    let mut canvas = image::DynamicImage::new_rgba8(side as u32, side as u32);
+    for (left, top, decoded) in &placed {
+        image::imageops::overlay(
+            &mut canvas,
+            decoded,
+            (left + offset_x).round() as i64,
+            (offset_y - top).round() as i64,
+        );
+    }
+    Ok(canvas)
+}
+
+pub fn decode(image: &RasterGlyphImage) -> Result<image::DynamicImage, Error> {
+    match image.format {
+        RasterImageFormat::PNG => {
+            let mut reader = image::ImageReader::new(Cursor::new(image.data));
+            reader.set_format(image::ImageFormat::Png);
+            reader.decode().map_err(Into::into)
+        }
+        RasterImageFormat::BitmapPremulBgra32 => {
+            let mut data = image.data.to_vec();
+            for pixel in data.chunks_exact_mut(4) {
+                let alpha = pixel[3] as u32;
+                pixel.swap(0, 2);
+                if alpha != 0 {
+                    for channel in &mut pixel[..3] {
+                        *channel = (*channel as u32 * 255 / alpha).min(255) as u8;
+                    }
+                }
+            }
+            image::RgbaImage::from_raw(image.width as u32, image.height as u32, data)
+                .map(image::DynamicImage::ImageRgba8)
+                .ok_or_else(|| Error::Font("truncated BGRA bitmap".to_owned()))
+        }
+        format => Err(Error::Font(format!("unsupported bitmap format {format:?}"))),
+    }
+}
@@ -0,0 +1,135 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    Error,
+    error::ErrorKind,
+    manifest::{Failure, Manifest},
+};
+
+/// When a build should be considered failed.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FailurePolicy {
+    /// Maximum number of missing emojis per vendor
+    pub max_missing: Option<usize>,
+    /// Fail if any emoji failed with one of these kinds (except missing ones)
+    pub fail_on: Vec<ErrorKind>,
+    /// Write a JSON report of all failures to this path
+    pub report: Option<PathBuf>,
+}
+
+/// Failures of a build, grouped by vendor and [`ErrorKind`].
+#[derive(Debug, Default, serde::Serialize)]
+pub struct Report<'a> {
+    vendors: BTreeMap<&'a str, VendorReport<'a>>,
+}
+
+#[derive(Debug, Default, serde::Serialize)]
+struct VendorReport<'a> {
+    /// Number of emojis that were produced
+    emojis: usize,
+    /// Number of emojis that weren't available (see [`Error::is_missing`])
+    missing: usize,
+    /// Number of emojis the vendor has no image for (see [`Manifest::unavailable`])
+    unavailable: usize,
+    /// Number of emojis that failed for other reasons, per kind
+    kinds: BTreeMap<ErrorKind, usize>,
+    failures: Vec<&'a Failure>,
+}
+
+impl<'a> Report<'a> {
+    /// Adds the results of a vendor.
+    ///
+    /// Emojis that failed in multiple outputs of the same vendor are only counted once.
+    pub fn add(&mut self, vendor: &'a str, manifest: &'a Manifest) {
+        let report = self.vendors.entry(vendor).or_default();
+        report.emojis = report.emojis.max(manifest.emojis.len());
+        report.unavailable = report.unavailable.max(manifest.unavailable.len());
+
+        let mut seen: BTreeSet<_> = report
+            .failures
+            .iter()
+            .map(|&it| (it.emoji.unified.as_str(), it.kind))
+            .collect();
+        for failure in &manifest.failures {
+            if !seen.insert((failure.emoji.unified.as_str(), failure.kind)) {
+                continue;
+            }
+            // missing emojis are only subject to `max_missing`
+            if failure.missing {
+                report.missing += 1;
+            } else {
+                *report.kinds.entry(failure.kind).or_default() += 1;
+            }
+            report.failures.push(failure);
+        }
+    }
+
+    /// Prints one line per failed emoji to stderr.
+    pub fn print_failures(&self) {
+        for (vendor, report) in &self.vendors {
+            for failure in &report.failures {
+                eprintln!(
+                    "{vendor}: :{}: ({}) failed: {}",
+                    failure.emoji.short_name, failure.emoji.unified, failure.error
+                );
+            }
+        }
+    }
+
+    /// Prints a table with the number of failures per vendor and kind.
+    pub fn print_summary(&self) {
+        let kinds: BTreeSet<ErrorKind> = self
+            .vendors
+            .values()
+            .flat_map(|it| it.kinds.keys().copied())
+            .collect();
+
+        let mut header = format!("{:<10} {:>7} {:>7} {:>7}", "vendor", "ok", "n/a", "missing");
+        for kind in &kinds {
+            header += &format!(" {:>9}", kind.to_string());
+        }
+        println!("{header}");
+        for (vendor, report) in &self.vendors {
+            let mut row = format!(
+                "{vendor:<10} {:>7} {:>7} {:>7}",
+                report.emojis, report.unavailable, report.missing
+            );
+            for kind in &kinds {
+                row += &format!(
+                    " {:>9}",
+                    report.kinds.get(kind).copied().unwrap_or_default()
+                );
+            }
+            println!("{row}");
+        }
+    }
+
+    /// Checks the thresholds of `policy` and returns a description of all violations.
+    pub fn violations(&self, policy: &FailurePolicy) -> Vec<String> {
+        let mut violations = Vec::new();
+        for (vendor, report) in &self.vendors {
+            if let Some(max) = policy.max_missing {
+                if report.missing > max {
+                    violations.push(format!(
+                        "{vendor}: {} emojis are missing (allowed: {max})",
+                        report.missing
+                    ));
+                }
+            }
+            for kind in &policy.fail_on {
+                if let Some(count) = report.kinds.get(kind) {
+                    violations.push(format!("{vendor}: {count} emojis failed with {kind}"));
+                }
+            }
+        }
+        violations
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        fs_err::write(path, serde_json::to_vec_pretty(self)?).map_err(Into::into)
+    }
+}
@@ -1,21 +1,92 @@
-use fast_image_resize::{PixelType, Resizer, images::TypedImageRef};
+use fast_image_resize::{
+    FilterType, MulDiv, PixelType, ResizeAlg, Resizer, create_srgb_mapper,
+    images::{Image, TypedImageRef},
+};
 use image::EncodableLayout;
 
-use crate::Error;
+use crate::{
+    Error,
+    config::{ResizeFilter, ResizeSettings},
+};
 
+/// Scales `image` to `width`x`height`.
+///
+/// Colors are premultiplied with their alpha before the convolution and divided afterwards, so
+/// transparent pixels don't darken the edges. With [`ResizeSettings::linear`], the image is
+/// converted to linear light (16 bit) first.
 pub fn resize(
-    options: &fast_image_resize::ResizeOptions,
+    settings: &ResizeSettings,
     image: &image::RgbaImage,
-    size: u32,
-) -> Result<fast_image_resize::images::Image<'static>, Error> {
-    let mut dst_image =
-        fast_image_resize::images::Image::new(size, size, fast_image_resize::PixelType::U8x4);
+    width: u32,
+    height: u32,
+) -> Result<Image<'static>, Error> {
+    let src_size = image.width().max(image.height());
+    // alpha is (de-)multiplied explicitly
+    let options = fast_image_resize::ResizeOptions::new()
+        .resize_alg(algorithm(settings.filter, src_size, width.max(height)))
+        .use_alpha(false);
+    let mul_div = MulDiv::default();
+    let src = ImageWrap(image);
 
-    Resizer::new().resize(&ImageWrap(image), &mut dst_image, options)?;
+    if !settings.linear {
+        let mut premultiplied = Image::new(image.width(), image.height(), PixelType::U8x4);
+        mul_div
+            .multiply_alpha(&src, &mut premultiplied)
+            .expect("the images have the same dimensions");
+        let mut dst_image = Image::new(width, height, PixelType::U8x4);
+        Resizer::new().resize(&premultiplied, &mut dst_image, &options)?;
+        mul_div
+            .divide_alpha_inplace(&mut dst_image)
+            .expect("the image has an alpha channel");
+        return Ok(dst_image);
+    }
 
+    let srgb = create_srgb_mapper();
+    let mut linear = Image::new(image.width(), image.height(), PixelType::U16x4);
+    srgb.forward_map(&src, &mut linear)
+        .expect("the images have the same dimensions");
+    mul_div
+        .multiply_alpha_inplace(&mut linear)
+        .expect("the image has an alpha channel");
+    let mut resized = Image::new(width, height, PixelType::U16x4);
+    Resizer::new().resize(&linear, &mut resized, &options)?;
+    mul_div
+        .divide_alpha_inplace(&mut resized)
+        .expect("the image has an alpha channel");
+    let mut dst_image = Image::new(width, height, PixelType::U8x4);
+    srgb.backward_map(&resized, &mut dst_image)
+        .expect("the images have the same dimensions");
     Ok(dst_image)
 }
 
+/// The resize algorithm for `filter` when scaling from `src_size` to `dst_size`.
+///
+/// [`ResizeFilter::Auto`] uses Catmull-Rom for upscaling and Lanczos3 for small downscale
+/// factors (below 2). Larger factors use Mitchell, as Lanczos3 produces visible ringing around
+/// the outlines there.
+pub fn algorithm(filter: ResizeFilter, src_size: u32, dst_size: u32) -> ResizeAlg {
+    let filter = match filter {
+        ResizeFilter::Auto => {
+            let factor = src_size as f32 / dst_size as f32;
+            if factor <= 1.0 {
+                FilterType::CatmullRom
+            } else if factor < 2.0 {
+                FilterType::Lanczos3
+            } else {
+                FilterType::Mitchell
+            }
+        }
+        ResizeFilter::Lanczos3 => FilterType::Lanczos3,
+        ResizeFilter::CatmullRom => FilterType::CatmullRom,
+        ResizeFilter::Mitchell => FilterType::Mitchell,
+        ResizeFilter::Bilinear => FilterType::Bilinear,
+        ResizeFilter::Box => FilterType::Box,
+        ResizeFilter::Supersampling => return ResizeAlg::SuperSampling(FilterType::Lanczos3, 2),
+        ResizeFilter::Nearest => return ResizeAlg::Nearest,
+    };
+    ResizeAlg::Convolution(filter)
+}
+
 struct ImageWrap<'a>(&'a image::RgbaImage);
 
 impl fast_image_resize::IntoImageView for ImageWrap<'_> {
@@ -0,0 +1,47 @@
+use rustybuzz::{GlyphBuffer, ShapePlan, UnicodeBuffer, shape_with_plan};
+
+use crate::{EmojiImage, Error};
+
+/// Shapes emojis with a font to find the glyph(s) of an emoji.
+pub struct Shaper<'a> {
+    face: rustybuzz::Face<'a>,
+    plan: ShapePlan,
+}
+
+impl<'a> Shaper<'a> {
+    pub fn new(data: &'a [u8], face_index: u32) -> Option<Self> {
+        let face = rustybuzz::Face::from_slice(data, face_index)?;
+        let plan = ShapePlan::new(&face, rustybuzz::Direction::LeftToRight, None, None, &[]);
+        Some(Self { face, plan })
+    }
+
+    pub fn face(&self) -> &rustybuzz::Face<'a> {
+        &self.face
+    }
+
+    /// Shapes the codepoints of `emoji`. Ideally, this results in a single glyph, but fonts
+    /// might not have ligatures for all sequences.
+    pub fn shape(&self, emoji: &EmojiImage) -> Result<GlyphBuffer, Error> {
+        let mut unicode_buffer = UnicodeBuffer::new();
+        let mut utf8_buffer = [0u8; 64];
+        unicode_buffer.push_str(parse_hex(emoji.unified, &mut utf8_buffer));
+        let glyphs = shape_with_plan(&self.face, &self.plan, unicode_buffer);
+        if glyphs.is_empty() {
+            return Err(Error::NoEmoji);
+        }
+        Ok(glyphs)
+    }
+}
+
+fn parse_hex<'a>(hex: &str, buffer: &'a mut [u8]) -> &'a mut str {
+    let mut size = 0;
+    for c in hex.split('-') {
+        let c = u32::from_str_radix(c, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .unwrap();
+        size += c.encode_utf8(&mut buffer[size..]).len();
+    }
+    // this will always work
+    std::str::from_utf8_mut(&mut buffer[..size]).unwrap()
+}
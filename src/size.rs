@@ -0,0 +1,378 @@
+use std::{fmt, str::FromStr};
+
+/// A requested output size.
+///
+/// Sizes are written as `22` (square), `28x22` (width and height), or `x22` (only a height, the
+/// width follows the aspect ratio of the source). Square sizes are serialized as numbers.
+/// Sizes derived from a density are serialized with it (e.g. `44@2x` or `33@hdpi`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Size {
+    /// `None` to preserve the aspect ratio of the source
+    pub width: Option<u32>,
+    pub height: u32,
+    /// The density this size was derived from (see [`Size::scaled`])
+    pub density: Option<Density>,
+}
+
+/// A scale factor of a base size and how it's named in paths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Density {
+    /// Scale in thousandths (`1500` = 1.5x)
+    pub millis: u32,
+    pub naming: DensityNaming,
+}
+
+/// How densities are named in the `{density}` placeholder.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    clap::ValueEnum,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum DensityNaming {
+    /// Scale suffixes as used by iOS (none for 1x, `@2x`, `@3x`)
+    #[default]
+    Ios,
+    /// Android buckets (`ldpi`, `mdpi`, `hdpi`, `xhdpi`, `xxhdpi`, `xxxhdpi`)
+    Android,
+}
+
+/// Android's density buckets by their scale in thousandths.
+const ANDROID_BUCKETS: [(u32, &str); 6] = [
+    (750, "ldpi"),
+    (1000, "mdpi"),
+    (1500, "hdpi"),
+    (2000, "xhdpi"),
+    (3000, "xxhdpi"),
+    (4000, "xxxhdpi"),
+];
+
+impl Size {
+    pub const fn square(size: u32) -> Self {
+        Self {
+            width: Some(size),
+            height: size,
+            density: None,
+        }
+    }
+
+    /// Scales this (base) size by `density`, rounding to the nearest pixel.
+    pub fn scaled(&self, density: Density) -> Self {
+        let scale = |px: u32| ((px as u64 * density.millis as u64 + 500) / 1000).max(1) as u32;
+        Self {
+            width: self.width.map(scale),
+            height: scale(self.height),
+            density: Some(density),
+        }
+    }
+
+    pub fn is_square(&self) -> bool {
+        self.width == Some(self.height)
+    }
+
+    /// The dimensions of an image for a source with the aspect ratio `width / height`.
+    pub fn dimensions(&self, aspect_ratio: f32) -> (u32, u32) {
+        let width = self
+            .width
+            .unwrap_or_else(|| ((self.height as f32 * aspect_ratio).round() as u32).max(1));
+        (width, self.height)
+    }
+
+    /// The factor this size is larger than `base` by if both axes are scaled by the same
+    /// integer (e.g. `44x88` is `2` times `22x44`).
+    pub fn multiple_of(&self, base: &Size) -> Option<u32> {
+        if self.height % base.height != 0 {
+            return None;
+        }
+        let factor = self.height / base.height;
+        match (self.width, base.width) {
+            (Some(width), Some(base_width)) if width == base_width * factor => Some(factor),
+            (None, None) => Some(factor),
+            _ => None,
+        }
+    }
+}
+
+impl From<u32> for Size {
+    fn from(size: u32) -> Self {
+        Self::square(size)
+    }
+}
+
+impl Density {
+    /// Creates a density from a scale factor (e.g. `1.5`).
+    ///
+    /// Fails for Android if there's no bucket for the scale.
+    pub fn new(scale: f32, naming: DensityNaming) -> Result<Self, String> {
+        if !scale.is_finite() || scale <= 0.0 {
+            return Err(format!("invalid density {scale}"));
+        }
+        let density = Self {
+            millis: (scale * 1000.0).round() as u32,
+            naming,
+        };
+        if naming == DensityNaming::Android && density.android_bucket().is_none() {
+            return Err(format!("there's no Android density bucket for {scale}x"));
+        }
+        Ok(density)
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.millis as f32 / 1000.0
+    }
+
+    /// The value of the `{density}` placeholder (e.g. `@2x` or `xhdpi`).
+    pub fn suffix(&self) -> String {
+        match self.naming {
+            DensityNaming::Ios if self.millis == 1000 => String::new(),
+            DensityNaming::Ios => format!("@{}x", self.scale()),
+            DensityNaming::Android => self.android_bucket().unwrap_or_default().to_owned(),
+        }
+    }
+
+    fn android_bucket(&self) -> Option<&'static str> {
+        ANDROID_BUCKETS
+            .iter()
+            .find(|(millis, _)| *millis == self.millis)
+            .map(|(_, name)| *name)
+    }
+}
+
+impl fmt::Display for Density {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.naming {
+            DensityNaming::Ios => write!(f, "{}x", self.scale()),
+            DensityNaming::Android => f.write_str(self.android_bucket().unwrap_or_default()),
+        }
+    }
+}
+
+impl FromStr for Density {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(scale) = s.strip_suffix('x') {
+            let scale = scale
+                .parse()
+                .map_err(|_| format!("invalid density '{s}'"))?;
+            return Self::new(scale, DensityNaming::Ios);
+        }
+        ANDROID_BUCKETS
+            .iter()
+            .find(|(_, name)| *name == s)
+            .map(|&(millis, _)| Self {
+                millis,
+                naming: DensityNaming::Android,
+            })
+            .ok_or_else(|| format!("invalid density '{s}', expected e.g. 2x or xhdpi"))
+    }
+}
+
+/// Formats the dimensions only, the density is available through [`Size::density`].
+impl fmt::Display for Size {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.width {
+            Some(width) if width == self.height => write!(f, "{width}"),
+            Some(width) => write!(f, "{width}x{}", self.height),
+            None => write!(f, "x{}", self.height),
+        }
+    }
+}
+
+impl FromStr for Size {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((size, density)) = s.split_once('@') {
+            return Ok(Self {
+                density: Some(density.parse()?),
+                ..size.parse()?
+            });
+        }
+        let invalid = || format!("invalid size '{s}', expected 22, 28x22, or x22");
+        let parse = |it: &str| match it.trim().parse::<u32>() {
+            Ok(0) | Err(_) => Err(invalid()),
+            Ok(it) => Ok(it),
+        };
+        match s.split_once(['x', 'X']) {
+            None => parse(s).map(Self::square),
+            Some(("", height)) => Ok(Self {
+                width: None,
+                height: parse(height)?,
+                density: None,
+            }),
+            Some((width, height)) => Ok(Self {
+                width: Some(parse(width)?),
+                height: parse(height)?,
+                density: None,
+            }),
+        }
+    }
+}
+
+impl serde::Serialize for Size {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.density {
+            Some(density) => serializer.collect_str(&format_args!("{self}@{density}")),
+            None if self.is_square() => serializer.serialize_u32(self.height),
+            None => serializer.collect_str(self),
+        }
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Size {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Value {
+            Square(u32),
+            Text(String),
+        }
+
+        match Value::deserialize(deserializer)? {
+            Value::Square(0) => Err(serde::de::Error::custom(
+                "invalid size '0', expected 22, 28x22, or x22",
+            )),
+            Value::Square(size) => Ok(Self::square(size)),
+            Value::Text(text) => text.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn size(width: Option<u32>, height: u32) -> Size {
+        Size {
+            width,
+            height,
+            density: None,
+        }
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!("22".parse(), Ok(Size::square(22)));
+        assert_eq!("28x22".parse(), Ok(size(Some(28), 22)));
+        assert_eq!("28X22".parse(), Ok(size(Some(28), 22)));
+        assert_eq!("x22".parse(), Ok(size(None, 22)));
+        for invalid in [
+            "", "0", "-1", "x", "x0", "22x", "0x22", "ax22", "28x22x1", "1.5",
+        ] {
+            assert!(invalid.parse::<Size>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for s in ["22", "28x22", "x22"] {
+            assert_eq!(s.parse::<Size>().unwrap().to_string(), s);
+        }
+        assert_eq!(size(Some(22), 22).to_string(), "22");
+    }
+
+    #[test]
+    fn serde() {
+        assert_eq!(serde_json::to_string(&Size::square(22)).unwrap(), "22");
+        assert_eq!(
+            serde_json::to_string(&size(Some(28), 22)).unwrap(),
+            r#""28x22""#
+        );
+        assert_eq!(
+            serde_json::from_str::<Size>("22").unwrap(),
+            Size::square(22)
+        );
+        assert_eq!(
+            serde_json::from_str::<Size>(r#""x22""#).unwrap(),
+            size(None, 22)
+        );
+        assert!(serde_json::from_str::<Size>(r#""22x""#).is_err());
+        assert!(serde_json::from_str::<Size>("0").is_err());
+        assert!(serde_json::from_str::<Size>(r#""0""#).is_err());
+    }
+
+    fn density(scale: f32, naming: DensityNaming) -> Density {
+        Density::new(scale, naming).unwrap()
+    }
+
+    #[test]
+    fn density_names() {
+        assert_eq!(density(1.0, DensityNaming::Ios).suffix(), "");
+        assert_eq!(density(1.5, DensityNaming::Ios).suffix(), "@1.5x");
+        assert_eq!(density(2.0, DensityNaming::Ios).suffix(), "@2x");
+        assert_eq!(density(0.75, DensityNaming::Android).suffix(), "ldpi");
+        assert_eq!(density(1.0, DensityNaming::Android).suffix(), "mdpi");
+        assert_eq!(density(4.0, DensityNaming::Android).suffix(), "xxxhdpi");
+        assert!(Density::new(1.25, DensityNaming::Android).is_err());
+        assert!(Density::new(0.0, DensityNaming::Ios).is_err());
+        assert!(Density::new(-1.0, DensityNaming::Ios).is_err());
+        assert!(Density::new(f32::NAN, DensityNaming::Ios).is_err());
+    }
+
+    #[test]
+    fn scaled() {
+        let scaled = |base: Size, scale| base.scaled(density(scale, DensityNaming::Ios));
+        assert_eq!(scaled(Size::square(22), 1.5).height, 33);
+        assert_eq!(scaled(Size::square(22), 1.5).width, Some(33));
+        // 16.5 is rounded up
+        assert_eq!(scaled(Size::square(22), 0.75).height, 17);
+        assert_eq!(scaled(Size::square(22), 3.0).height, 66);
+        assert_eq!(
+            scaled(size(Some(28), 22), 1.5),
+            Size {
+                width: Some(42),
+                height: 33,
+                density: Some(density(1.5, DensityNaming::Ios)),
+            }
+        );
+        assert_eq!(scaled(size(None, 22), 2.0).width, None);
+        assert_eq!(scaled(Size::square(1), 0.1).height, 1);
+    }
+
+    #[test]
+    fn parse_density() {
+        let ios = Size::square(22).scaled(density(2.0, DensityNaming::Ios));
+        assert_eq!("44@2x".parse(), Ok(ios));
+        let android = Size::square(22).scaled(density(1.5, DensityNaming::Android));
+        assert_eq!("33@hdpi".parse(), Ok(android));
+        for invalid in ["44@", "44@2", "44@0x", "44@udpi", "@2x", "44@2x@2x"] {
+            assert!(invalid.parse::<Size>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn serde_density() {
+        let size = Size::square(22).scaled(density(1.5, DensityNaming::Ios));
+        let json = serde_json::to_string(&size).unwrap();
+        assert_eq!(json, r#""33@1.5x""#);
+        assert_eq!(serde_json::from_str::<Size>(&json).unwrap(), size);
+        // paths only contain the pixels
+        assert_eq!(size.to_string(), "33");
+    }
+
+    #[test]
+    fn dimensions() {
+        assert_eq!(size(None, 22).dimensions(2.0), (44, 22));
+        assert_eq!(size(None, 22).dimensions(0.01), (1, 22));
+        assert_eq!(size(Some(28), 22).dimensions(2.0), (28, 22));
+    }
+
+    #[test]
+    fn multiple_of() {
+        assert_eq!(Size::square(44).multiple_of(&Size::square(22)), Some(2));
+        assert_eq!(size(Some(56), 44).multiple_of(&size(Some(28), 22)), Some(2));
+        assert_eq!(size(None, 66).multiple_of(&size(None, 22)), Some(3));
+        assert_eq!(Size::square(33).multiple_of(&Size::square(22)), None);
+        assert_eq!(size(None, 44).multiple_of(&Size::square(22)), None);
+    }
+}
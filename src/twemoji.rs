@@ -2,6 +2,7 @@ use std::path::Path;
 
 use crate::{
     EmojiImage,
+    manifest::OutputFile,
     provider::{Provider, SvgProvider},
 };
 
@@ -21,28 +22,34 @@ impl<'a> Twemoji<'a> {
 
 impl SvgProvider for Twemoji<'_> {
     fn read_svg(&self, emoji: &EmojiImage) -> Result<usvg::Tree, crate::Error> {
-        let mut path = self.svg_dir.join(strip_zeroes(emoji.unified));
-        path.set_extension("svg");
-
-        let data = fs_err::read(path).or_else(|e| match emoji.non_qualified {
-            Some(nq) => {
-                let mut path = self.svg_dir.join(strip_zeroes(nq));
-                path.set_extension("svg");
-                fs_err::read(path)
-            }
-            None => Err(e),
-        })?;
+        let data = self.read_source(emoji)?;
         usvg::Tree::from_data(&data, &self.options).map_err(Into::into)
     }
 }
 
 impl Provider for Twemoji<'_> {
+    fn read_source(&self, emoji: &EmojiImage) -> Result<Vec<u8>, crate::Error> {
+        let mut path = self.svg_dir.join(strip_zeroes(emoji.unified));
+        path.set_extension("svg");
+
+        fs_err::read(path)
+            .or_else(|e| match emoji.non_qualified {
+                Some(nq) => {
+                    let mut path = self.svg_dir.join(strip_zeroes(nq));
+                    path.set_extension("svg");
+                    fs_err::read(path)
+                }
+                None => Err(e),
+            })
+            .map_err(Into::into)
+    }
+
     fn transform(
         &self,
         options: &crate::Options,
         emoji: &EmojiImage,
-        sizes: &[u32],
-    ) -> Result<(), crate::Error> {
+        sizes: &[crate::Size],
+    ) -> Result<Vec<OutputFile>, crate::Error> {
         self.transform_svg(options, emoji, sizes)
     }
 }
@@ -0,0 +1,622 @@
+use std::fmt::Write;
+
+use tiny_skia::Transform;
+use ttf_parser::{GlyphId, RgbaColor, colr};
+
+use crate::{
+    EmojiImage, Error, Options, Size, fit, manifest::OutputFile, provider::Provider,
+    shaping::Shaper,
+};
+
+/// Renders emojis from vector color fonts (`COLR` v0/v1 or `SVG ` tables).
+///
+/// `COLR` glyphs are translated to SVG, so both kinds are rendered with resvg at every size
+/// without any raster loss.
+pub struct VectorFont<'a> {
+    shaper: Shaper<'a>,
+}
+
+/// A shaped glyph and its origin in font units (relative to the start of the run).
+struct PlacedGlyph<'a> {
+    source: GlyphSource<'a>,
+    x: f32,
+    y: f32,
+}
+
+enum GlyphSource<'a> {
+    /// A `COLR` glyph translated to SVG (in font units, y pointing up)
+    Colr(String),
+    /// An SVG document of the `SVG ` table (in font units, y pointing down)
+    Svg {
+        data: &'a [u8],
+        /// The element of this glyph, if the document contains multiple glyphs
+        id: Option<String>,
+    },
+}
+
+/// The glyphs of an emoji and the square they're centered in.
+struct Glyphs<'a> {
+    glyphs: Vec<PlacedGlyph<'a>>,
+    /// Size of the square canvas in font units
+    side: f32,
+    /// Origin of the run on the canvas (y pointing down, at the baseline)
+    origin: (f32, f32),
+}
+
+impl<'a> VectorFont<'a> {
+    /// Returns `None` if the font can't be parsed or has neither a `COLR` nor an `SVG ` table.
+    pub fn new(data: &'a [u8], face_index: u32) -> Option<Self> {
+        let shaper = Shaper::new(data, face_index)?;
+        let tables = shaper.face().tables();
+        if tables.colr.is_none() && tables.svg.is_none() {
+            return None;
+        }
+        Some(Self { shaper })
+    }
+
+    fn glyphs(&self, emoji: &EmojiImage) -> Result<Glyphs<'a>, Error> {
+        let face = self.shaper.face();
+        let buffer = self.shaper.shape(emoji)?;
+
+        let mut glyphs = Vec::with_capacity(buffer.len());
+        let mut pen = 0.0;
+        for (info, position) in buffer.glyph_infos().iter().zip(buffer.glyph_positions()) {
+            let id = GlyphId(info.glyph_id as u16);
+            if id.0 == 0 {
+                return Err(Error::NoEmoji);
+            }
+            // glyphs without color data (e.g. a ZWJ of a sequence without a ligature) only
+            // advance the pen
+            if let Some(source) = self.glyph_source(id) {
+                glyphs.push(PlacedGlyph {
+                    source,
+                    x: pen + position.x_offset as f32,
+                    y: position.y_offset as f32,
+                });
+            }
+            pen += position.x_advance as f32;
+        }
+        if glyphs.is_empty() {
+            return Err(Error::NoEmoji);
+        }
+
+        let ascender = face.ascender() as f32;
+        let height = ascender - face.descender() as f32;
+        let side = pen.max(height);
+        Ok(Glyphs {
+            glyphs,
+            side,
+            origin: ((side - pen) / 2.0, (side - height) / 2.0 + ascender),
+        })
+    }
+
+    fn glyph_source(&self, id: GlyphId) -> Option<GlyphSource<'a>> {
+        let face = self.shaper.face();
+        if face.is_color_glyph(id) {
+            let mut painter = GlyphPainter::new(face);
+            if face
+                .paint_color_glyph(id, 0, RgbaColor::new(0, 0, 0, 255), &mut painter)
+                .is_some()
+            {
+                return Some(GlyphSource::Colr(painter.finish()));
+            }
+        }
+        if let Some(document) = face.glyph_svg_image(id) {
+            return Some(GlyphSource::Svg {
+                data: document.data,
+                id: (document.start_glyph_id != document.end_glyph_id)
+                    .then(|| format!("glyph{}", id.0)),
+            });
+        }
+        None
+    }
+}
+
+impl Provider for VectorFont<'_> {
+    fn read_source(&self, emoji: &EmojiImage) -> Result<Vec<u8>, Error> {
+        let glyphs = self.glyphs(emoji)?;
+        let mut source = Vec::new();
+        for glyph in &glyphs.glyphs {
+            source.extend_from_slice(&glyph.x.to_le_bytes());
+            source.extend_from_slice(&glyph.y.to_le_bytes());
+            match glyph.source {
+                GlyphSource::Colr(ref svg) => source.extend_from_slice(svg.as_bytes()),
+                GlyphSource::Svg { data, ref id } => {
+                    source.extend_from_slice(data);
+                    source.extend_from_slice(id.as_deref().unwrap_or_default().as_bytes());
+                }
+            }
+        }
+        Ok(source)
+    }
+
+    fn transform(
+        &self,
+        options: &Options,
+        emoji: &EmojiImage,
+        sizes: &[Size],
+    ) -> Result<Vec<OutputFile>, Error> {
+        let glyphs = self.glyphs(emoji)?;
+        let usvg_options = usvg::Options::default();
+        let trees = glyphs
+            .glyphs
+            .iter()
+            .map(|it| match it.source {
+                GlyphSource::Colr(ref svg) => usvg::Tree::from_str(svg, &usvg_options),
+                GlyphSource::Svg { data, .. } => usvg::Tree::from_data(data, &usvg_options),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // place the glyphs on the canvas (in font units, y pointing down)
+        let mut layers = Vec::with_capacity(trees.len());
+        let mut bounds: Option<(f32, f32, f32, f32)> = None;
+        for (glyph, tree) in glyphs.glyphs.iter().zip(&trees) {
+            let x = glyphs.origin.0 + glyph.x;
+            let y = glyphs.origin.1 - glyph.y;
+            let (transform, node) = match glyph.source {
+                GlyphSource::Colr(_) => (Transform::from_row(1.0, 0.0, 0.0, -1.0, x, y), None),
+                GlyphSource::Svg { ref id, .. } => {
+                    let node = match id {
+                        Some(id) => Some(tree.node_by_id(id).ok_or(Error::NoEmoji)?),
+                        None => None,
+                    };
+                    (Transform::from_translate(x, y), node)
+                }
+            };
+
+            let bbox = match node {
+                Some(node) => node.abs_layer_bounding_box(),
+                None => Some(tree.root().abs_layer_bounding_box()),
+            };
+            if let Some(bbox) = bbox.and_then(|it| it.transform(transform)) {
+                bounds = Some(match bounds {
+                    Some((left, top, right, bottom)) => (
+                        left.min(bbox.left()),
+                        top.min(bbox.top()),
+                        right.max(bbox.right()),
+                        bottom.max(bbox.bottom()),
+                    ),
+                    None => (bbox.left(), bbox.top(), bbox.right(), bbox.bottom()),
+                });
+            }
+            layers.push((tree, node, transform));
+        }
+        let content = match bounds {
+            Some((left, top, right, bottom)) if options.fit.trim => {
+                tiny_skia::Rect::from_ltrb(left, top, right, bottom)
+            }
+            _ => tiny_skia::Rect::from_xywh(0.0, 0.0, glyphs.side, glyphs.side),
+        }
+        .ok_or(Error::NoEmoji)?;
+
+        let mut outputs = Vec::with_capacity(sizes.len() * options.outputs().len());
+        for &size in sizes {
+            let dimensions = fit::dimensions(size, content.width(), content.height());
+            let mut pixmap = tiny_skia::Pixmap::new(dimensions.0, dimensions.1).unwrap();
+            let fit = fit::transform(&options.fit, content, dimensions);
+            for &(tree, node, transform) in &layers {
+                let transform = fit.pre_concat(transform);
+                match node {
+                    Some(node) => render_node(node, transform, &mut pixmap.as_mut()),
+                    None => resvg::render(tree, transform, &mut pixmap.as_mut()),
+                }
+            }
+            outputs.extend(options.write_skia(size, emoji, pixmap)?);
+        }
+        Ok(outputs)
+    }
+}
+
+/// Renders a single node with the transforms of its ancestors.
+fn render_node(node: &usvg::Node, transform: Transform, pixmap: &mut tiny_skia::PixmapMut) {
+    // resvg renders the node relative to its bounding box and only applies its own transform
+    let parent = match node {
+        usvg::Node::Group(group) => group
+            .abs_transform()
+            .pre_concat(group.transform().invert().unwrap_or_default()),
+        other => other.abs_transform(),
+    };
+    let Some(bbox) = node.abs_layer_bounding_box() else {
+        return;
+    };
+    resvg::render_node(
+        node,
+        transform
+            .pre_concat(parent)
+            .pre_translate(bbox.x(), bbox.y()),
+        pixmap,
+    );
+}
+
+/// Translates a `COLR` glyph to SVG (based on usvg's text rendering).
+struct GlyphPainter<'a, 'f> {
+    face: &'f ttf_parser::Face<'a>,
+    svg: String,
+    path: String,
+    /// Bounding box of `path` in font units
+    path_bbox: Option<ttf_parser::Rect>,
+    next_id: usize,
+    transform: ttf_parser::Transform,
+    outline_transform: ttf_parser::Transform,
+    transforms: Vec<ttf_parser::Transform>,
+}
+
+impl<'a, 'f> GlyphPainter<'a, 'f> {
+    fn new(face: &'f ttf_parser::Face<'a>) -> Self {
+        Self {
+            face,
+            svg: String::from(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink"><g>"#,
+            ),
+            path: String::new(),
+            path_bbox: None,
+            next_id: 0,
+            transform: ttf_parser::Transform::default(),
+            outline_transform: ttf_parser::Transform::default(),
+            transforms: Vec::new(),
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.svg.push_str("</g></svg>");
+        self.svg
+    }
+
+    fn next_id(&mut self, prefix: &str) -> String {
+        self.next_id += 1;
+        format!("{prefix}{}", self.next_id)
+    }
+
+    /// Writes the current outline with the given fill attributes.
+    fn fill_outline(&mut self, fill: &str) {
+        write!(
+            self.svg,
+            r#"<path {fill}{} d="{}"/>"#,
+            transform_attr("transform", self.outline_transform),
+            self.path
+        )
+        .unwrap();
+    }
+
+    fn paint_solid(&mut self, color: RgbaColor) {
+        let fill = format!(
+            r#"fill="{}" fill-opacity="{}""#,
+            rgb(color),
+            color.alpha as f32 / 255.0
+        );
+        self.fill_outline(&fill);
+    }
+
+    fn write_stops(&mut self, stops: colr::GradientStopsIter) {
+        // stops aren't necessarily sorted
+        let mut stops: Vec<_> = stops.collect();
+        stops.sort_by(|a, b| a.stop_offset.total_cmp(&b.stop_offset));
+        for stop in stops {
+            write!(
+                self.svg,
+                r#"<stop offset="{}" stop-color="{}" stop-opacity="{}"/>"#,
+                stop.stop_offset,
+                rgb(stop.color),
+                stop.color.alpha as f32 / 255.0
+            )
+            .unwrap();
+        }
+    }
+
+    fn paint_linear_gradient(&mut self, gradient: colr::LinearGradient<'a>) {
+        let id = self.next_id("lg");
+        // SVG has no rotation point (p2), so p1 is projected onto the line perpendicular to p0p2
+        let (dx, dy) = (gradient.y2 - gradient.y0, gradient.x0 - gradient.x2);
+        let length = dx * dx + dy * dy;
+        let (x1, y1) = if length > 0.0 {
+            let dot = (gradient.x1 - gradient.x0) * dx + (gradient.y1 - gradient.y0) * dy;
+            (
+                gradient.x0 + dx * dot / length,
+                gradient.y0 + dy * dot / length,
+            )
+        } else {
+            (gradient.x1, gradient.y1)
+        };
+
+        write!(
+            self.svg,
+            r#"<linearGradient id="{id}" x1="{}" y1="{}" x2="{x1}" y2="{y1}" gradientUnits="userSpaceOnUse" spreadMethod="{}"{}>"#,
+            gradient.x0,
+            gradient.y0,
+            spread_method(gradient.extend),
+            transform_attr("gradientTransform", self.paint_transform()),
+        )
+        .unwrap();
+        let face = self.face;
+        self.write_stops(gradient.stops(0, face.variation_coordinates()));
+        self.svg.push_str("</linearGradient>");
+        self.fill_outline(&format!(r#"fill="url(#{id})""#));
+    }
+
+    fn paint_radial_gradient(&mut self, gradient: colr::RadialGradient<'a>) {
+        let id = self.next_id("rg");
+        write!(
+            self.svg,
+            r#"<radialGradient id="{id}" cx="{}" cy="{}" r="{}" fx="{}" fy="{}" fr="{}" gradientUnits="userSpaceOnUse" spreadMethod="{}"{}>"#,
+            gradient.x1,
+            gradient.y1,
+            gradient.r1,
+            gradient.x0,
+            gradient.y0,
+            gradient.r0,
+            spread_method(gradient.extend),
+            transform_attr("gradientTransform", self.paint_transform()),
+        )
+        .unwrap();
+        let face = self.face;
+        self.write_stops(gradient.stops(0, face.variation_coordinates()));
+        self.svg.push_str("</radialGradient>");
+        self.fill_outline(&format!(r#"fill="url(#{id})""#));
+    }
+
+    /// SVG has no sweep gradients, so they're approximated with thin wedges around the center
+    /// (clipped to the outline).
+    fn paint_sweep_gradient(&mut self, gradient: colr::SweepGradient<'a>) {
+        const SEGMENTS: u16 = 360;
+
+        let face = self.face;
+        let mut stops: Vec<_> = gradient.stops(0, face.variation_coordinates()).collect();
+        stops.sort_by(|a, b| a.stop_offset.total_cmp(&b.stop_offset));
+        let (cx, cy) = (gradient.center_x, gradient.center_y);
+        let Some(radius) = self.paint_radius(cx, cy).filter(|_| !stops.is_empty()) else {
+            return;
+        };
+        // the wedges are triangles, so their outer edge has to be outside the outline
+        let radius = radius * 1.01 + 1.0;
+
+        let path = std::mem::take(&mut self.path);
+        self.clip_with_path(&path);
+        self.path = path;
+        // anti-aliasing would leave seams between the wedges, the clip path is still smooth
+        write!(
+            self.svg,
+            r#"<g shape-rendering="crispEdges"{}>"#,
+            transform_attr("transform", self.transform)
+        )
+        .unwrap();
+        // angles are counter-clockwise in half turns
+        let (start, end) = (gradient.start_angle * 180.0, gradient.end_angle * 180.0);
+        let step = 360.0 / SEGMENTS as f32;
+        let point = |angle: f32| {
+            let (sin, cos) = angle.to_radians().sin_cos();
+            (cx + radius * cos, cy + radius * sin)
+        };
+        for i in 0..SEGMENTS {
+            let angle = i as f32 * step;
+            let color = sweep_color(&stops, start, end, gradient.extend, angle + step / 2.0);
+            let (x0, y0) = point(angle);
+            let (x1, y1) = point(angle + step);
+            write!(
+                self.svg,
+                r#"<path fill="{}" fill-opacity="{}" d="M {cx} {cy} L {x0} {y0} L {x1} {y1} Z"/>"#,
+                rgb(color),
+                color.alpha as f32 / 255.0
+            )
+            .unwrap();
+        }
+        self.svg.push_str("</g></g>");
+    }
+
+    /// The distance from (`x`, `y`) in paint space to the farthest corner of the outline.
+    fn paint_radius(&self, x: f32, y: f32) -> Option<f32> {
+        let bbox = self.path_bbox?;
+        let ts = to_skia(self.transform)
+            .invert()?
+            .pre_concat(to_skia(self.outline_transform));
+        [
+            (bbox.x_min, bbox.y_min),
+            (bbox.x_max, bbox.y_min),
+            (bbox.x_max, bbox.y_max),
+            (bbox.x_min, bbox.y_max),
+        ]
+        .into_iter()
+        .map(|(px, py)| {
+            let (px, py) = (px as f32, py as f32);
+            let tx = ts.sx * px + ts.kx * py + ts.tx;
+            let ty = ts.ky * px + ts.sy * py + ts.ty;
+            (tx - x).hypot(ty - y)
+        })
+        .reduce(f32::max)
+    }
+
+    /// The transform of a paint relative to the outline it fills.
+    fn paint_transform(&self) -> ttf_parser::Transform {
+        let outline = to_skia(self.outline_transform).invert().unwrap_or_default();
+        let ts = outline.pre_concat(to_skia(self.transform));
+        ttf_parser::Transform::new(ts.sx, ts.ky, ts.kx, ts.sy, ts.tx, ts.ty)
+    }
+
+    fn clip_with_path(&mut self, path: &str) {
+        let id = self.next_id("cp");
+        write!(
+            self.svg,
+            r#"<clipPath id="{id}"><path{} d="{path}"/></clipPath><g clip-path="url(#{id})">"#,
+            transform_attr("transform", self.outline_transform),
+        )
+        .unwrap();
+    }
+}
+
+impl<'a> colr::Painter<'a> for GlyphPainter<'a, '_> {
+    fn outline_glyph(&mut self, glyph_id: GlyphId) {
+        self.path.clear();
+        let mut builder = PathBuilder(&mut self.path);
+        self.path_bbox = self.face.outline_glyph(glyph_id, &mut builder);
+        if self.path_bbox.is_none() {
+            return;
+        }
+        self.outline_transform = self.transform;
+    }
+
+    fn paint(&mut self, paint: colr::Paint<'a>) {
+        match paint {
+            colr::Paint::Solid(color) => self.paint_solid(color),
+            colr::Paint::LinearGradient(it) => self.paint_linear_gradient(it),
+            colr::Paint::RadialGradient(it) => self.paint_radial_gradient(it),
+            colr::Paint::SweepGradient(it) => self.paint_sweep_gradient(it),
+        }
+    }
+
+    fn push_clip(&mut self) {
+        let path = std::mem::take(&mut self.path);
+        self.clip_with_path(&path);
+        self.path = path;
+    }
+
+    fn push_clip_box(&mut self, clip_box: colr::ClipBox) {
+        let path = format!(
+            "M {0} {1} L {2} {1} L {2} {3} L {0} {3} Z",
+            clip_box.x_min, clip_box.y_min, clip_box.x_max, clip_box.y_max
+        );
+        self.clip_with_path(&path);
+    }
+
+    fn pop_clip(&mut self) {
+        self.svg.push_str("</g>");
+    }
+
+    fn push_layer(&mut self, mode: colr::CompositeMode) {
+        use colr::CompositeMode;
+
+        // Porter-Duff modes can't be expressed in SVG
+        let mode = match mode {
+            CompositeMode::Screen => "screen",
+            CompositeMode::Overlay => "overlay",
+            CompositeMode::Darken => "darken",
+            CompositeMode::Lighten => "lighten",
+            CompositeMode::ColorDodge => "color-dodge",
+            CompositeMode::ColorBurn => "color-burn",
+            CompositeMode::HardLight => "hard-light",
+            CompositeMode::SoftLight => "soft-light",
+            CompositeMode::Difference => "difference",
+            CompositeMode::Exclusion => "exclusion",
+            CompositeMode::Multiply => "multiply",
+            CompositeMode::Hue => "hue",
+            CompositeMode::Saturation => "saturation",
+            CompositeMode::Color => "color",
+            CompositeMode::Luminosity => "luminosity",
+            _ => "normal",
+        };
+        write!(
+            self.svg,
+            r#"<g style="mix-blend-mode: {mode}; isolation: isolate">"#
+        )
+        .unwrap();
+    }
+
+    fn pop_layer(&mut self) {
+        self.svg.push_str("</g>");
+    }
+
+    fn push_transform(&mut self, transform: ttf_parser::Transform) {
+        self.transforms.push(self.transform);
+        self.transform = ttf_parser::Transform::combine(self.transform, transform);
+    }
+
+    fn pop_transform(&mut self) {
+        if let Some(transform) = self.transforms.pop() {
+            self.transform = transform;
+        }
+    }
+}
+
+struct PathBuilder<'a>(&'a mut String);
+
+impl ttf_parser::OutlineBuilder for PathBuilder<'_> {
+    fn move_to(&mut self, x: f32, y: f32) {
+        write!(self.0, "M {x} {y} ").unwrap();
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        write!(self.0, "L {x} {y} ").unwrap();
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        write!(self.0, "Q {x1} {y1} {x} {y} ").unwrap();
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        write!(self.0, "C {x1} {y1} {x2} {y2} {x} {y} ").unwrap();
+    }
+
+    fn close(&mut self) {
+        self.0.push_str("Z ");
+    }
+}
+
+fn rgb(color: RgbaColor) -> String {
+    format!("rgb({}, {}, {})", color.red, color.green, color.blue)
+}
+
+/// The color of a sweep gradient from `start` to `end` at `angle` (all in degrees).
+fn sweep_color(
+    stops: &[colr::ColorStop],
+    start: f32,
+    end: f32,
+    extend: colr::GradientExtend,
+    angle: f32,
+) -> RgbaColor {
+    let t = if (end - start).abs() > f32::EPSILON {
+        (angle - start) / (end - start)
+    } else if angle < start {
+        f32::MIN
+    } else {
+        f32::MAX
+    };
+    let t = match extend {
+        // offsets outside of the stops use the first/last color
+        colr::GradientExtend::Pad => t,
+        colr::GradientExtend::Repeat => t.rem_euclid(1.0),
+        colr::GradientExtend::Reflect => {
+            let t = t.rem_euclid(2.0);
+            if t > 1.0 { 2.0 - t } else { t }
+        }
+    };
+
+    let first = stops[0];
+    if t <= first.stop_offset {
+        return first.color;
+    }
+    for pair in stops.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        if t <= b.stop_offset {
+            let f = (t - a.stop_offset) / (b.stop_offset - a.stop_offset);
+            let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * f).round() as u8;
+            return RgbaColor::new(
+                mix(a.color.red, b.color.red),
+                mix(a.color.green, b.color.green),
+                mix(a.color.blue, b.color.blue),
+                mix(a.color.alpha, b.color.alpha),
+            );
+        }
+    }
+    stops[stops.len() - 1].color
+}
+
+fn spread_method(extend: colr::GradientExtend) -> &'static str {
+    match extend {
+        colr::GradientExtend::Pad => "pad",
+        colr::GradientExtend::Repeat => "repeat",
+        colr::GradientExtend::Reflect => "reflect",
+    }
+}
+
+/// Formats a transform attribute (including the leading space) or nothing for the identity.
+fn transform_attr(name: &str, ts: ttf_parser::Transform) -> String {
+    if ts.is_default() {
+        return String::new();
+    }
+    format!(
+        r#" {name}="matrix({} {} {} {} {} {})""#,
+        ts.a, ts.b, ts.c, ts.d, ts.e, ts.f
+    )
+}
+
+fn to_skia(ts: ttf_parser::Transform) -> Transform {
+    Transform::from_row(ts.a, ts.b, ts.c, ts.d, ts.e, ts.f)
+}
@@ -0,0 +1,32 @@
+use std::fmt::Display;
+
+#[derive(
+    Debug, Clone, PartialEq, Eq, Hash, clap::ValueEnum, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Vendor {
+    Apple,
+    Twitter,
+    Google,
+    Facebook,
+}
+
+impl Vendor {
+    pub const ALL: [Vendor; 4] = [
+        Vendor::Apple,
+        Vendor::Twitter,
+        Vendor::Google,
+        Vendor::Facebook,
+    ];
+}
+
+impl Display for Vendor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Vendor::Apple => write!(f, "apple"),
+            Vendor::Twitter => write!(f, "twitter"),
+            Vendor::Google => write!(f, "google"),
+            Vendor::Facebook => write!(f, "facebook"),
+        }
+    }
+}
@@ -5,32 +5,37 @@ use crate::optimize::{OptimizableImage, Optimizer};
 pub struct Webp;
 
 impl Optimizer for Webp {
+    fn name(&self) -> &'static str {
+        "webp"
+    }
+
     fn optimize_fir(
         &self,
         options: &crate::Options,
         image: fast_image_resize::images::Image,
-        path: &std::path::Path,
-    ) -> Result<(), crate::Error> {
-        optimize(options, image, path)
+    ) -> Result<Vec<u8>, crate::Error> {
+        optimize(options, image)
     }
 
     fn optimize_skia(
         &self,
         options: &crate::Options,
         image: tiny_skia::Pixmap,
-        path: &std::path::Path,
-    ) -> Result<(), crate::Error> {
-        optimize(options, image, path)
+    ) -> Result<Vec<u8>, crate::Error> {
+        optimize(options, image)
     }
 }
 
 fn optimize(
     options: &crate::Options,
     image: impl OptimizableImage,
-    path: &std::path::Path,
-) -> Result<(), crate::Error> {
-    let encoded = ::webp::Encoder::from_rgba(image.data(), image.width(), image.height())
+) -> Result<Vec<u8>, crate::Error> {
+    let width = image.width();
+    let height = image.height();
+    // WEBP expects unmultiplied alpha
+    let data = image.into_data();
+    let encoded = ::webp::Encoder::from_rgba(&data, width, height)
         .encode_advanced(&options.webp)
         .map_err(crate::Error::Webp)?;
-    fs_err::write(path, encoded.deref()).map_err(Into::into)
+    Ok(encoded.deref().to_vec())
 }